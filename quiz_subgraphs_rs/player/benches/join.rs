@@ -0,0 +1,59 @@
+use async_graphql::ID;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use player::InMemoryDb;
+use tokio::runtime::Runtime;
+
+// Total number of players already registered before joining
+const TOTAL_PLAYERS: [usize; 3] = [1_000, 10_000, 50_000];
+const PLAYERS_PER_QUIZ: usize = 500;
+
+async fn populated_db(total_players: usize) -> InMemoryDb {
+    let in_memory_db = InMemoryDb::default();
+    for i in 0..total_players {
+        let quiz_id = ID::from((i / PLAYERS_PER_QUIZ).to_string());
+        in_memory_db
+            .create_player(format!("player-{i}"), &quiz_id)
+            .await
+            .expect("usernames are unique");
+    }
+    in_memory_db
+}
+
+fn join(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let quiz_id = ID::from("0");
+
+    let mut group = c.benchmark_group("create_player");
+    for total_players in TOTAL_PLAYERS {
+        let in_memory_db = runtime.block_on(populated_db(total_players));
+        let mut joined = 0;
+        group.bench_with_input(
+            BenchmarkId::from_parameter(total_players),
+            &in_memory_db,
+            |b, in_memory_db| {
+                b.to_async(&runtime).iter(|| {
+                    joined += 1;
+                    in_memory_db.create_player(format!("new-player-{joined}"), &quiz_id)
+                })
+            },
+        );
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("players_for_quiz");
+    for total_players in TOTAL_PLAYERS {
+        let in_memory_db = runtime.block_on(populated_db(total_players));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(total_players),
+            &in_memory_db,
+            |b, in_memory_db| {
+                b.to_async(&runtime)
+                    .iter(|| async { black_box(in_memory_db.players_for_quiz(&quiz_id).await) })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, join);
+criterion_main!(benches);
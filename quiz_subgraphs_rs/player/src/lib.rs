@@ -0,0 +1,38 @@
+mod models;
+mod quiz_client;
+
+use async_graphql::Schema;
+use axum::Router;
+use subgraph_core::RouterBuilder;
+
+use models::{InMemoryBroker, MutationRoot, QueryRoot, SubscriptionRoot};
+use quiz_client::{QuizClient, DEFAULT_QUIZ_URL};
+
+pub use models::{InMemoryDb, Player};
+
+fn schema() -> Schema<QueryRoot, MutationRoot, SubscriptionRoot> {
+    subgraph_core::schema_builder(QueryRoot, MutationRoot, SubscriptionRoot).finish()
+}
+
+/// SDL of the subgraph with its federation directives, as composed by the router
+pub fn schema_sdl() -> String {
+    subgraph_core::schema_sdl(&schema())
+}
+
+// Join codes are checked against the quiz subgraph at `QUIZ_URL`
+pub fn app() -> Router {
+    app_with_quiz_url(std::env::var("QUIZ_URL").unwrap_or_else(|_| DEFAULT_QUIZ_URL.to_string()))
+}
+
+/// Check the join codes against the quiz subgraph at `quiz_url`
+pub fn app_with_quiz_url(quiz_url: String) -> Router {
+    let in_memory_db = InMemoryDb::default();
+    let in_memory_broker = InMemoryBroker::default();
+    let schema = subgraph_core::schema_builder(QueryRoot, MutationRoot, SubscriptionRoot)
+        .data(in_memory_db)
+        .data(in_memory_broker)
+        .data(QuizClient::new(quiz_url))
+        .finish();
+
+    RouterBuilder::new(schema).finish()
+}
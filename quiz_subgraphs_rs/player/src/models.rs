@@ -1,96 +1,173 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use async_graphql::{Context, Object, SimpleObject, Subscription, ID};
+use async_graphql::{Context, Object, SimpleObject, Subscription, Union, ID};
 use futures_util::{Stream, StreamExt};
+use subgraph_core::Channels;
 use tokio::sync::{
-    broadcast::{self},
+    broadcast::{self, error::RecvError},
     RwLock,
 };
-use tokio_stream::wrappers::BroadcastStream;
 use uuid::Uuid;
 
+use crate::quiz_client::QuizClient;
+
+// Deltas must not be dropped silently, so the lobby channel keeps more than the latest message
+const LOBBY_CHANNEL_CAPACITY: usize = 64;
+
 #[derive(Default)]
-pub(crate) struct InMemoryDb {
-    players: RwLock<HashMap<ID, Player>>,
+pub struct InMemoryDb {
+    players: RwLock<PlayerStore>,
+}
+
+/// Players with the indexes needed to join and list a lobby without scanning every player
+#[derive(Default)]
+struct PlayerStore {
+    players: HashMap<ID, Player>,
+    // player ids by quiz id
+    by_quiz: HashMap<ID, HashSet<ID>>,
+    usernames: HashSet<String>,
 }
 
 impl InMemoryDb {
     pub(crate) async fn get_player(&self, player_id: &ID) -> Option<Player> {
-        self.players.read().await.get(player_id).cloned()
+        self.players.read().await.players.get(player_id).cloned()
     }
 
-    pub(crate) async fn create_player(&self, username: String, quiz_id: &ID) -> Option<Player> {
-        let mut players = self.players.write().await;
-        if players.values().any(|p| p.name == username) {
+    pub async fn create_player(&self, username: String, quiz_id: &ID) -> Option<Player> {
+        let mut store = self.players.write().await;
+        if store.usernames.contains(&username) {
             // Conflict username already took
             return None;
         }
-        let player_id = Uuid::new_v4();
+        let player_id = ID::from(Uuid::new_v4().to_string());
 
         let new_player = Player {
-            id: ID::from(player_id.to_string()),
-            name: username,
+            id: player_id.clone(),
+            name: username.clone(),
             quiz_id: quiz_id.clone(),
         };
 
-        players.insert(ID::from(player_id.to_string()), new_player.clone());
+        store.usernames.insert(username);
+        store
+            .by_quiz
+            .entry(quiz_id.clone())
+            .or_default()
+            .insert(player_id.clone());
+        store.players.insert(player_id, new_player.clone());
 
         Some(new_player)
     }
 
-    pub(crate) async fn players_for_quiz(&self, quiz_id: &ID) -> Vec<Player> {
-        self.players
-            .read()
-            .await
-            .values()
-            .filter_map(|p| {
-                if &p.quiz_id == quiz_id {
-                    Some(p.clone())
-                } else {
-                    None
-                }
+    pub(crate) async fn remove_player(&self, player_id: &ID) -> Option<Player> {
+        let mut store = self.players.write().await;
+        let player = store.players.remove(player_id)?;
+
+        store.usernames.remove(&player.name);
+        if let Some(quiz_players) = store.by_quiz.get_mut(&player.quiz_id) {
+            quiz_players.remove(player_id);
+            if quiz_players.is_empty() {
+                store.by_quiz.remove(&player.quiz_id);
+            }
+        }
+
+        Some(player)
+    }
+
+    pub async fn players_for_quiz(&self, quiz_id: &ID) -> Vec<Player> {
+        let store = self.players.read().await;
+        store
+            .by_quiz
+            .get(quiz_id)
+            .map(|player_ids| {
+                player_ids
+                    .iter()
+                    .filter_map(|player_id| store.players.get(player_id).cloned())
+                    .collect()
             })
-            .collect()
+            .unwrap_or_default()
     }
 }
 
-#[derive(Default)]
 pub(crate) struct InMemoryBroker {
-    players: RwLock<HashMap<ID, broadcast::Sender<Vec<Player>>>>,
+    players: Channels<LobbyEvent>,
 }
 
 impl InMemoryBroker {
-    pub(crate) async fn subscribe_new_players(
-        &self,
-        quiz_id: &ID,
-    ) -> impl Stream<Item = Vec<Player>> {
-        {
-            let players = self.players.read().await;
-            if let Some(player) = players.get(quiz_id) {
-                return BroadcastStream::new(player.subscribe())
-                    .filter_map(|e| async move { e.ok() })
-                    .boxed();
-            }
-        }
-        let (tx, rx) = broadcast::channel(2);
-        self.players.write().await.insert(quiz_id.clone(), tx);
-        let players_stream = BroadcastStream::new(rx)
-            .filter_map(|e| async move { e.ok() })
-            .boxed();
+    pub(crate) async fn subscribe_lobby(&self, quiz_id: &ID) -> broadcast::Receiver<LobbyEvent> {
+        self.players.receiver(quiz_id).await
+    }
 
-        players_stream
+    pub(crate) async fn player_joined(&self, quiz_id: &ID, player: Player) {
+        self.players
+            .send(quiz_id, LobbyEvent::PlayerJoined(PlayerJoined { player }))
+            .await;
     }
 
-    pub(crate) async fn new_players(&self, quiz_id: &ID, players: Vec<Player>) {
-        if let Some(players_broker) = self.players.read().await.get(quiz_id) {
-            let _err = players_broker.send(players);
+    pub(crate) async fn player_left(&self, quiz_id: &ID, player: Player) {
+        self.players
+            .send(quiz_id, LobbyEvent::PlayerLeft(PlayerLeft { player }))
+            .await;
+    }
+}
+
+impl Default for InMemoryBroker {
+    fn default() -> Self {
+        Self {
+            players: Channels::new(LOBBY_CHANNEL_CAPACITY),
         }
     }
 }
 
+/// Stream of lobby events for a quiz, starting with a snapshot of the players already there.
+///
+/// The broadcast receiver is created before the snapshot is read so no join can be missed,
+/// joins already part of the snapshot are not sent twice. If the subscriber lags behind
+/// and deltas are lost, a fresh snapshot is sent instead.
+fn lobby_events<'a>(
+    in_memory_db: &'a InMemoryDb,
+    quiz_id: ID,
+    mut receiver: broadcast::Receiver<LobbyEvent>,
+) -> impl Stream<Item = LobbyEvent> + 'a {
+    async_stream::stream! {
+        let players = in_memory_db.players_for_quiz(&quiz_id).await;
+        let mut in_snapshot: HashSet<ID> = players.iter().map(|p| p.id.clone()).collect();
+        yield LobbyEvent::Snapshot(LobbySnapshot { players });
+
+        loop {
+            match receiver.recv().await {
+                Ok(LobbyEvent::PlayerJoined(joined)) if in_snapshot.remove(&joined.player.id) => {}
+                Ok(event) => yield event,
+                Err(RecvError::Lagged(_)) => {
+                    let players = in_memory_db.players_for_quiz(&quiz_id).await;
+                    in_snapshot = players.iter().map(|p| p.id.clone()).collect();
+                    yield LobbyEvent::Snapshot(LobbySnapshot { players });
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+}
+
+/// Full list of players after each lobby event. The first snapshot is only the starting point,
+/// like before the lobby events only changes are sent
+fn player_lists<'a>(
+    events: impl Stream<Item = LobbyEvent> + 'a,
+) -> impl Stream<Item = Vec<Player>> + 'a {
+    events
+        .scan(Vec::new(), |players: &mut Vec<Player>, event| {
+            match event {
+                LobbyEvent::Snapshot(snapshot) => *players = snapshot.players,
+                LobbyEvent::PlayerJoined(joined) => players.push(joined.player),
+                LobbyEvent::PlayerLeft(left) => players.retain(|p| p.id != left.player.id),
+            }
+            futures_util::future::ready(Some(players.clone()))
+        })
+        .skip(1)
+}
+
 pub(crate) struct QueryRoot;
 
-#[Object]
+#[Object(name = "Query")]
 impl QueryRoot {
     async fn player<'ctx>(
         &self,
@@ -111,6 +188,8 @@ impl QueryRoot {
         in_memory_db.players_for_quiz(&quiz_id).await
     }
 
+    // `quiz_id` is part of the key of the entity, players are found by id alone
+    #[allow(unused_variables)]
     #[graphql(entity)]
     async fn find_player_by_id_and_quiz_id<'ctx>(
         &self,
@@ -119,36 +198,64 @@ impl QueryRoot {
         quiz_id: ID,
     ) -> Option<Player> {
         let in_memory_db: &InMemoryDb = ctx.data_unchecked();
-        let player = in_memory_db.get_player(&id).await;
-        player
+        in_memory_db.get_player(&id).await
     }
 }
 
 pub(crate) struct SubscriptionRoot;
 
-#[Subscription]
+#[Subscription(name = "Subscription")]
 impl SubscriptionRoot {
+    /// Full list of players for a quiz, sent again on every change
     async fn players_for_a_quiz<'ctx>(
         &self,
         ctx: &Context<'ctx>,
         quiz_id: ID,
-    ) -> async_graphql::Result<impl Stream<Item = Vec<Player>>> {
+    ) -> async_graphql::Result<impl Stream<Item = Vec<Player>> + 'ctx> {
+        let in_memory_db: &InMemoryDb = ctx.data_unchecked();
         let in_memory_broker: &InMemoryBroker = ctx.data_unchecked();
-        let player_stream = in_memory_broker.subscribe_new_players(&quiz_id).await;
-        Ok(player_stream)
+        let receiver = in_memory_broker.subscribe_lobby(&quiz_id).await;
+        Ok(player_lists(lobby_events(in_memory_db, quiz_id, receiver)))
+    }
+
+    /// Players in the lobby of a quiz: a snapshot first, then a `PlayerJoined`/`PlayerLeft` event per change
+    async fn lobby_events_for_a_quiz<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        quiz_id: ID,
+    ) -> async_graphql::Result<impl Stream<Item = LobbyEvent> + 'ctx> {
+        let in_memory_db: &InMemoryDb = ctx.data_unchecked();
+        let in_memory_broker: &InMemoryBroker = ctx.data_unchecked();
+        let receiver = in_memory_broker.subscribe_lobby(&quiz_id).await;
+        Ok(lobby_events(in_memory_db, quiz_id, receiver))
     }
 }
 
 pub(crate) struct MutationRoot;
 
-#[Object]
+#[Object(name = "Mutation")]
 impl MutationRoot {
+    /// Join a quiz with either its ID or the join code of its game in progress
     async fn create_player<'ctx>(
         &self,
         ctx: &Context<'ctx>,
         user_name: String,
-        quiz_id: ID,
+        quiz_id: Option<ID>,
+        code: Option<String>,
     ) -> async_graphql::Result<Player> {
+        let quiz_id = match (quiz_id, code) {
+            (Some(quiz_id), None) => quiz_id,
+            (None, Some(code)) => {
+                let quiz_client: &QuizClient = ctx.data_unchecked();
+                quiz_client
+                    .quiz_for_code(&code)
+                    .await
+                    .map_err(async_graphql::Error::new)?
+                    .ok_or_else(|| async_graphql::Error::new("no game in progress for this code"))?
+            }
+            _ => return Err("either quizId or code is needed".into()),
+        };
+
         let in_memory_db: &InMemoryDb = ctx.data_unchecked();
 
         let new_player = in_memory_db
@@ -156,18 +263,158 @@ impl MutationRoot {
             .await
             .ok_or_else(|| async_graphql::Error::new("cannot create a player"))?;
 
-        let players = in_memory_db.players_for_quiz(&quiz_id).await;
-
         let in_memory_broker: &InMemoryBroker = ctx.data_unchecked();
-        in_memory_broker.new_players(&quiz_id, players).await;
+        in_memory_broker
+            .player_joined(&quiz_id, new_player.clone())
+            .await;
 
         Ok(new_player)
     }
+
+    /// Leave the quiz the player (from the `player` header) joined
+    async fn leave_quiz<'ctx>(&self, ctx: &Context<'ctx>) -> async_graphql::Result<Player> {
+        let player_id = subgraph_core::player_id(ctx)?;
+
+        let in_memory_db: &InMemoryDb = ctx.data_unchecked();
+        let player = in_memory_db
+            .remove_player(player_id)
+            .await
+            .ok_or_else(|| async_graphql::Error::new("player not found"))?;
+
+        let in_memory_broker: &InMemoryBroker = ctx.data_unchecked();
+        in_memory_broker
+            .player_left(&player.quiz_id, player.clone())
+            .await;
+
+        Ok(player)
+    }
 }
 
 #[derive(Clone, SimpleObject, Debug)]
-pub(crate) struct Player {
+pub struct Player {
     pub(crate) id: ID,
     pub(crate) name: String,
     pub(crate) quiz_id: ID,
 }
+
+#[derive(Clone, SimpleObject, Debug)]
+pub(crate) struct LobbySnapshot {
+    pub(crate) players: Vec<Player>,
+}
+
+#[derive(Clone, SimpleObject, Debug)]
+pub(crate) struct PlayerJoined {
+    pub(crate) player: Player,
+}
+
+#[derive(Clone, SimpleObject, Debug)]
+pub(crate) struct PlayerLeft {
+    pub(crate) player: Player,
+}
+
+#[derive(Clone, Union, Debug)]
+pub(crate) enum LobbyEvent {
+    Snapshot(LobbySnapshot),
+    PlayerJoined(PlayerJoined),
+    PlayerLeft(PlayerLeft),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUIZ_ID: &str = "0";
+
+    fn names(players: &[Player]) -> Vec<&str> {
+        players.iter().map(|player| player.name.as_str()).collect()
+    }
+
+    fn event_name(event: &LobbyEvent) -> String {
+        match event {
+            LobbyEvent::Snapshot(snapshot) => format!("snapshot {:?}", names(&snapshot.players)),
+            LobbyEvent::PlayerJoined(joined) => format!("joined {}", joined.player.name),
+            LobbyEvent::PlayerLeft(left) => format!("left {}", left.player.name),
+        }
+    }
+
+    // Create the player and send its join, like `MutationRoot::create_player`
+    async fn join(db: &InMemoryDb, broker: &InMemoryBroker, name: &str) -> Player {
+        let quiz_id = ID::from(QUIZ_ID);
+        let player = db.create_player(name.to_string(), &quiz_id).await.unwrap();
+        broker.player_joined(&quiz_id, player.clone()).await;
+        player
+    }
+
+    async fn leave(db: &InMemoryDb, broker: &InMemoryBroker, player: &Player) {
+        db.remove_player(&player.id).await.unwrap();
+        broker.player_left(&player.quiz_id, player.clone()).await;
+    }
+
+    #[tokio::test]
+    async fn lobby_starts_with_a_snapshot_then_sends_changes() {
+        let db = InMemoryDb::default();
+        let broker = InMemoryBroker::default();
+        join(&db, &broker, "alice").await;
+
+        let receiver = broker.subscribe_lobby(&QUIZ_ID.into()).await;
+        let events = lobby_events(&db, QUIZ_ID.into(), receiver);
+        let bob = join(&db, &broker, "bob").await;
+        leave(&db, &broker, &bob).await;
+        broker.players.close(&QUIZ_ID.into()).await;
+
+        let events: Vec<String> = events.map(|event| event_name(&event)).collect().await;
+        assert_eq!(events, ["snapshot [\"alice\"]", "joined bob", "left bob"]);
+    }
+
+    #[tokio::test]
+    async fn joins_already_in_the_snapshot_are_not_sent_again() {
+        let db = InMemoryDb::default();
+        let broker = InMemoryBroker::default();
+
+        // The receiver exists before alice joins, so her join is both in the snapshot and sent
+        let receiver = broker.subscribe_lobby(&QUIZ_ID.into()).await;
+        join(&db, &broker, "alice").await;
+        let events = lobby_events(&db, QUIZ_ID.into(), receiver);
+        broker.players.close(&QUIZ_ID.into()).await;
+
+        let events: Vec<String> = events.map(|event| event_name(&event)).collect().await;
+        assert_eq!(events, ["snapshot [\"alice\"]"]);
+    }
+
+    #[tokio::test]
+    async fn lagging_subscribers_get_a_new_snapshot() {
+        let db = InMemoryDb::default();
+        let broker = InMemoryBroker::default();
+
+        let receiver = broker.subscribe_lobby(&QUIZ_ID.into()).await;
+        let mut events = Box::pin(lobby_events(&db, QUIZ_ID.into(), receiver));
+        assert_eq!(event_name(&events.next().await.unwrap()), "snapshot []");
+        for i in 0..=LOBBY_CHANNEL_CAPACITY {
+            join(&db, &broker, &format!("player {i}")).await;
+        }
+
+        match events.next().await.unwrap() {
+            LobbyEvent::Snapshot(snapshot) => {
+                assert_eq!(snapshot.players.len(), LOBBY_CHANNEL_CAPACITY + 1)
+            }
+            event => panic!("expected a snapshot, got {}", event_name(&event)),
+        }
+    }
+
+    #[tokio::test]
+    async fn player_lists_are_sent_on_every_change() {
+        let db = InMemoryDb::default();
+        let broker = InMemoryBroker::default();
+        join(&db, &broker, "alice").await;
+
+        let receiver = broker.subscribe_lobby(&QUIZ_ID.into()).await;
+        let lists = player_lists(lobby_events(&db, QUIZ_ID.into(), receiver));
+        let bob = join(&db, &broker, "bob").await;
+        leave(&db, &broker, &bob).await;
+        broker.players.close(&QUIZ_ID.into()).await;
+
+        let lists: Vec<Vec<Player>> = lists.collect().await;
+        let lists: Vec<Vec<&str>> = lists.iter().map(|players| names(players)).collect();
+        assert_eq!(lists, [vec!["alice", "bob"], vec!["alice"]]);
+    }
+}
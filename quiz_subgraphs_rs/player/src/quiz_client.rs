@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use async_graphql::ID;
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Method, Request};
+use serde_json::{json, Value};
+
+/// Default URL of the quiz subgraph, which knows the join codes of the games
+pub(crate) const DEFAULT_QUIZ_URL: &str = "http://localhost:4005/";
+
+// Players joining with a code wait for the quiz subgraph, not forever when it's slow or down
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Client for the quiz subgraph
+pub(crate) struct QuizClient {
+    client: Client<HttpConnector>,
+    url: String,
+}
+
+impl QuizClient {
+    pub(crate) fn new(url: String) -> Self {
+        Self {
+            client: Client::new(),
+            url,
+        }
+    }
+
+    /// Quiz of the game in progress with this join code, `None` if there is no such game
+    pub(crate) async fn quiz_for_code(&self, code: &str) -> Result<Option<ID>, String> {
+        let body = json!({
+            "query": "query($code: String!) { gameByCode(code: $code) { id } }",
+            "variables": { "code": code },
+        });
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(&self.url)
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .map_err(|err| err.to_string())?;
+
+        let response = tokio::time::timeout(TIMEOUT, self.send(request))
+            .await
+            .map_err(|_| "the quiz subgraph didn't answer in time".to_string())??;
+        if let Some(error) = response["errors"][0]["message"].as_str() {
+            return Err(format!("error from the quiz subgraph: {error}"));
+        }
+
+        Ok(response["data"]["gameByCode"]["id"].as_str().map(ID::from))
+    }
+
+    async fn send(&self, request: Request<Body>) -> Result<Value, String> {
+        let response = self
+            .client
+            .request(request)
+            .await
+            .map_err(|err| format!("cannot reach the quiz subgraph: {err}"))?;
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(|err| err.to_string())?;
+        serde_json::from_slice(&body).map_err(|err| err.to_string())
+    }
+}
@@ -0,0 +1,5 @@
+// The player subgraph is the solution of the workshop, it must pass every exercise
+#[test]
+fn exercises() {
+    workshop_checker::check_player(env!("CARGO_BIN_EXE_player"));
+}
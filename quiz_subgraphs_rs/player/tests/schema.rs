@@ -0,0 +1,34 @@
+//! The router composes the supergraph from the schema files in `apollo_router`, they must be
+//! the SDL of this subgraph
+
+use std::path::Path;
+
+const SCHEMA_FILE: &str = "../../apollo_router/player_schema.graphql";
+
+#[test]
+fn schema_file_is_up_to_date() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(SCHEMA_FILE);
+    let sdl = player::schema_sdl();
+    if std::env::var_os("UPDATE_SCHEMA").is_some() {
+        std::fs::write(&path, &sdl).unwrap();
+        return;
+    }
+
+    let schema_file = std::fs::read_to_string(&path).unwrap();
+    if let Some((line, (expected, found))) = sdl
+        .lines()
+        .zip(schema_file.lines())
+        .enumerate()
+        .find(|(_, (expected, found))| expected != found)
+    {
+        panic!(
+            "{SCHEMA_FILE} is out of date at line {}, `{found}` instead of `{expected}`, \
+             update it with `UPDATE_SCHEMA=1 cargo test --test schema`",
+            line + 1
+        );
+    }
+    assert!(
+        sdl == schema_file,
+        "{SCHEMA_FILE} is out of date, update it with `UPDATE_SCHEMA=1 cargo test --test schema`"
+    );
+}
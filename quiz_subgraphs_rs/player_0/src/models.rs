@@ -1,28 +1,36 @@
 use std::collections::HashMap;
 
+#[allow(unused_imports)]
 use async_graphql::{Context, Object, SimpleObject, Subscription, ID};
+#[allow(unused_imports)]
 use futures_util::{stream, Stream, StreamExt};
 use tokio::sync::{
     broadcast::{self},
     RwLock,
 };
+#[allow(unused_imports)]
 use tokio_stream::wrappers::BroadcastStream;
+#[allow(unused_imports)]
 use uuid::Uuid;
 
 #[derive(Default)]
 pub(crate) struct InMemoryDb {
+    #[allow(dead_code)]
     players: RwLock<HashMap<ID, Player>>,
 }
 
 impl InMemoryDb {
+    #[allow(dead_code, unused_variables)]
     pub(crate) async fn get_player(&self, player_id: &ID) -> Option<Player> {
         todo!()
     }
 
+    #[allow(dead_code, unused_variables)]
     pub(crate) async fn create_player(&self, username: String, quiz_id: &ID) -> Option<Player> {
         todo!()
     }
 
+    #[allow(dead_code, unused_variables)]
     pub(crate) async fn players_for_quiz(&self, quiz_id: &ID) -> Vec<Player> {
         todo!()
     }
@@ -30,10 +38,12 @@ impl InMemoryDb {
 
 #[derive(Default)]
 pub(crate) struct InMemoryBroker {
+    #[allow(dead_code)]
     players: RwLock<HashMap<ID, broadcast::Sender<Vec<Player>>>>,
 }
 
 impl InMemoryBroker {
+    #[allow(dead_code, unused_variables)]
     pub(crate) async fn subscribe_new_players(
         &self,
         quiz_id: &ID,
@@ -41,6 +51,7 @@ impl InMemoryBroker {
         stream::empty()
     }
 
+    #[allow(dead_code, unused_variables)]
     pub(crate) async fn new_players(&self, quiz_id: &ID, players: Vec<Player>) {
         todo!()
     }
@@ -50,11 +61,13 @@ pub(crate) struct QueryRoot;
 
 #[Object]
 impl QueryRoot {
+    #[allow(unused_variables)]
     async fn test<'ctx>(&self, ctx: &Context<'ctx>, id: ID) -> String {
         String::from("test")
     }
 }
 
+#[allow(dead_code)]
 pub(crate) struct SubscriptionRoot;
 
 // #[Subscription]
@@ -68,6 +81,7 @@ pub(crate) struct SubscriptionRoot;
 //     }
 // }
 
+#[allow(dead_code)]
 pub(crate) struct MutationRoot;
 
 // #[Object]
@@ -1,16 +1,19 @@
 use std::collections::HashMap;
 
 use async_graphql::{Context, Object, SimpleObject, Subscription, ID};
+#[allow(unused_imports)]
 use futures_util::{stream, Stream, StreamExt};
 use tokio::sync::{
     broadcast::{self},
     RwLock,
 };
+#[allow(unused_imports)]
 use tokio_stream::wrappers::BroadcastStream;
 use uuid::Uuid;
 
 #[derive(Default)]
 pub(crate) struct InMemoryDb {
+    #[allow(dead_code)]
     players: RwLock<HashMap<ID, Player>>,
 }
 
@@ -31,6 +34,7 @@ impl InMemoryDb {
         })
     }
 
+    #[allow(unused_variables)]
     pub(crate) async fn players_for_quiz(&self, quiz_id: &ID) -> Vec<Player> {
         vec![Player::default()]
     }
@@ -38,10 +42,12 @@ impl InMemoryDb {
 
 #[derive(Default)]
 pub(crate) struct InMemoryBroker {
+    #[allow(dead_code)]
     players: RwLock<HashMap<ID, broadcast::Sender<Vec<Player>>>>,
 }
 
 impl InMemoryBroker {
+    #[allow(unused_variables)]
     pub(crate) async fn subscribe_new_players(
         &self,
         quiz_id: &ID,
@@ -49,6 +55,7 @@ impl InMemoryBroker {
         stream::iter(vec![vec![Player::default()], vec![Player::default()]])
     }
 
+    #[allow(dead_code, unused_variables)]
     pub(crate) async fn new_players(&self, quiz_id: &ID, players: Vec<Player>) {
         todo!()
     }
@@ -73,6 +80,7 @@ impl QueryRoot {
         in_memory_db.players_for_quiz(&quiz_id).await
     }
 
+    #[allow(unused_variables)]
     #[graphql(entity)]
     async fn find_player_by_id_and_quiz_id<'ctx>(
         &self,
@@ -0,0 +1,6 @@
+// Fails until every exercise is done, so it only runs when asked for
+#[test]
+#[ignore = "checks the workshop exercises, run it with `cargo test -- --ignored --nocapture`"]
+fn exercises() {
+    workshop_checker::check_player(env!("CARGO_BIN_EXE_player_1"));
+}
@@ -0,0 +1,101 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_graphql::ID;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use quiz::{InMemoryBroker, InMemoryDb, DEFAULT_LEADERBOARD_INTERVAL};
+use tokio::runtime::Runtime;
+use tokio_stream::StreamExt;
+
+const PLAYERS: [usize; 3] = [1_000, 5_000, 10_000];
+
+fn player_id(player: usize) -> ID {
+    ID::from(player.to_string())
+}
+
+// Questions of quiz "0" have four choices, players spread their answers over them
+fn choice_id(player: usize) -> ID {
+    ID::from((player % 4).to_string())
+}
+
+/// Quiz "0" from `data.json` with its first question opened and every player already on the leaderboard
+async fn started_quiz(players: usize) -> (Arc<InMemoryDb>, InMemoryBroker) {
+    let in_memory_db = Arc::new(InMemoryDb::default());
+    let in_memory_broker = InMemoryBroker::new(DEFAULT_LEADERBOARD_INTERVAL);
+    let quiz_id = ID::from("0");
+
+    in_memory_db.next_question(&quiz_id).await.unwrap();
+    for player in 0..players {
+        in_memory_db
+            .answer(
+                &player_id(player),
+                &quiz_id,
+                &ID::from("0"),
+                Some(&choice_id(player)),
+            )
+            .await
+            .unwrap();
+    }
+
+    (in_memory_db, in_memory_broker)
+}
+
+fn answer(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let quiz_id = ID::from("0");
+
+    let mut group = c.benchmark_group("answer_concurrently");
+    for players in PLAYERS {
+        group.throughput(Throughput::Elements(players as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(players),
+            &players,
+            |b, &players| {
+                // Players answer a question once, every iteration needs a new game
+                b.iter_custom(|iters| {
+                    let mut elapsed = Duration::ZERO;
+                    for _ in 0..iters {
+                        let (in_memory_db, in_memory_broker) =
+                            runtime.block_on(started_quiz(players));
+                        let in_memory_broker = Arc::new(in_memory_broker);
+                        // Keep a subscriber so leaderboards are really broadcast
+                        let mut leaderboard =
+                            runtime.block_on(in_memory_broker.subscribe_leaderboard(&quiz_id));
+                        runtime.spawn(async move { while leaderboard.next().await.is_some() {} });
+                        runtime
+                            .block_on(in_memory_db.next_question(&quiz_id))
+                            .unwrap();
+
+                        let start = Instant::now();
+                        let answers = (0..players).map(|player| {
+                            let in_memory_db = in_memory_db.clone();
+                            let in_memory_broker = in_memory_broker.clone();
+                            let quiz_id = quiz_id.clone();
+                            runtime.spawn(async move {
+                                in_memory_db
+                                    .answer(
+                                        &player_id(player),
+                                        &quiz_id,
+                                        &ID::from("1"),
+                                        Some(&choice_id(player)),
+                                    )
+                                    .await
+                                    .unwrap();
+                                in_memory_broker
+                                    .schedule_leaderboard(&in_memory_db, &quiz_id)
+                                    .await;
+                            })
+                        });
+                        runtime.block_on(futures_util::future::join_all(answers));
+                        elapsed += start.elapsed();
+                    }
+                    elapsed
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, answer);
+criterion_main!(benches);
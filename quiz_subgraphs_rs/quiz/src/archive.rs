@@ -0,0 +1,270 @@
+use std::time::Duration;
+
+use async_graphql::{SimpleObject, Union, ID};
+use chrono::{DateTime, Utc};
+use futures_util::Stream;
+
+use crate::history::GameHistory;
+use crate::models::{Player, Question, Quiz};
+use crate::question_bank::GameOptions;
+use crate::ranking::Ranking;
+use crate::report::GameReport;
+
+/// A finished game, with everything needed to replay it
+#[derive(Clone, Debug, SimpleObject)]
+pub(crate) struct PastGame {
+    /// Same as the session ID of the game
+    pub(crate) id: ID,
+    pub(crate) quiz_id: ID,
+    pub(crate) started_at: DateTime<Utc>,
+    pub(crate) finished_at: DateTime<Utc>,
+    /// Options the questions were drawn with, to play the same game again
+    pub(crate) options: GameOptions,
+    /// Players from the best to the worst one when the game ended
+    pub(crate) final_leaderboard: Vec<Player>,
+    #[graphql(skip)]
+    pub(crate) questions: Vec<ArchivedQuestion>,
+    // Sorted by time
+    #[graphql(skip)]
+    pub(crate) answers: Vec<ArchivedAnswer>,
+    // The game itself is dropped when the next one starts
+    #[graphql(skip)]
+    pub(crate) report: GameReport,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ArchivedQuestion {
+    pub(crate) question: Question,
+    // Time between the game start and the question being opened
+    pub(crate) opened_after: Duration,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ArchivedAnswer {
+    pub(crate) player_id: ID,
+    pub(crate) correct: bool,
+    // Time between the question being opened and the answer
+    pub(crate) answer_time: Duration,
+    // Time between the game start and the answer
+    pub(crate) answered_after: Duration,
+}
+
+/// Event sent when replaying a game
+#[derive(Clone, Debug, Union)]
+pub(crate) enum ReplayEvent {
+    Question(Question),
+    Leaderboard(ReplayedLeaderboard),
+}
+
+#[derive(Clone, Debug, SimpleObject)]
+pub(crate) struct ReplayedLeaderboard {
+    /// Players with their points at that time of the game, from the best to the worst one
+    pub(crate) list: Vec<Player>,
+}
+
+impl PastGame {
+    pub(crate) fn new(
+        id: &ID,
+        quiz: &Quiz,
+        started_at: DateTime<Utc>,
+        finished_at: DateTime<Utc>,
+        ranking: &Ranking,
+        history: &GameHistory,
+    ) -> Self {
+        let asked_questions: Vec<ArchivedQuestion> = history
+            .questions()
+            .iter()
+            .filter_map(|asked| {
+                let question = quiz.questions.iter().find(|q| q.id == asked.id)?;
+                Some(ArchivedQuestion {
+                    question: question.clone(),
+                    opened_after: asked.opened_after,
+                })
+            })
+            .collect();
+        let mut answers: Vec<ArchivedAnswer> = history
+            .answers()
+            .filter_map(|(player_id, answer)| {
+                let question = history
+                    .questions()
+                    .iter()
+                    .find(|asked| asked.id == answer.question_id)?;
+                Some(ArchivedAnswer {
+                    player_id: player_id.clone(),
+                    correct: answer.correct,
+                    answer_time: answer.answer_time,
+                    answered_after: question.opened_after + answer.answer_time,
+                })
+            })
+            .collect();
+        answers.sort_by_key(|answer| answer.answered_after);
+
+        Self {
+            report: GameReport::new(id, quiz, ranking, history),
+            id: id.clone(),
+            quiz_id: quiz.id.clone(),
+            started_at,
+            finished_at,
+            options: quiz.game_options.clone().unwrap_or_default(),
+            final_leaderboard: ranking
+                .iter()
+                .map(|player| Player::ranked(&quiz.id, player))
+                .collect(),
+            questions: asked_questions,
+            answers,
+        }
+    }
+
+    /// Send the questions and leaderboards again with the original timing divided by `speed`.
+    ///
+    /// Like during the game, the answers given within `leaderboard_interval` of each other are
+    /// part of the same leaderboard.
+    pub(crate) fn replay(
+        self,
+        speed: f64,
+        leaderboard_interval: Duration,
+    ) -> impl Stream<Item = ReplayEvent> {
+        async_stream::stream! {
+            let start = tokio::time::Instant::now();
+            let mut ranking = Ranking::default();
+            let mut questions = self.questions.into_iter().peekable();
+            let mut answers = self.answers.into_iter().peekable();
+
+            loop {
+                let next_question = questions.peek().map(|q| q.opened_after);
+                let next_answer = answers.peek().map(|a| a.answered_after);
+                match (next_question, next_answer) {
+                    (Some(question_at), answer_at)
+                        if answer_at.is_none_or(|answer_at| question_at <= answer_at) =>
+                    {
+                        let Some(asked) = questions.next() else { break };
+                        tokio::time::sleep_until(start + question_at.div_f64(speed)).await;
+                        ranking.snapshot_ranks();
+                        yield ReplayEvent::Question(asked.question);
+                    }
+                    (_, Some(answer_at)) => {
+                        let leaderboard_at = answer_at + leaderboard_interval;
+                        // Every answer until the leaderboard is sent is part of it
+                        while let Some(answer) = answers.next_if(|a| {
+                            a.answered_after <= leaderboard_at
+                                && questions
+                                    .peek()
+                                    .is_none_or(|q| a.answered_after < q.opened_after)
+                        }) {
+                            ranking.add_points(
+                                &answer.player_id,
+                                answer.correct as usize,
+                                answer.answer_time,
+                            );
+                        }
+                        tokio::time::sleep_until(start + leaderboard_at.div_f64(speed)).await;
+                        yield ReplayEvent::Leaderboard(ReplayedLeaderboard {
+                            list: ranking
+                                .iter()
+                                .map(|player| Player::ranked(&self.quiz_id, player))
+                                .collect(),
+                        });
+                    }
+                    _ => break,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+    use tokio::time::Instant;
+
+    use super::*;
+    use crate::models::DATA_FILE;
+    use crate::quiz_file::QuizFile;
+
+    const LEADERBOARD_INTERVAL: Duration = Duration::from_millis(500);
+
+    // A game of quiz "0" of `data.json`, its two questions opened at 0s and 10s
+    fn past_game() -> PastGame {
+        let quiz = QuizFile::parse(DATA_FILE).unwrap().into_quizzes().remove(0);
+        let now = Utc::now();
+        let mut game = PastGame::new(
+            &ID::from("session"),
+            &quiz,
+            now,
+            now,
+            &Ranking::default(),
+            &GameHistory::default(),
+        );
+        game.questions = quiz
+            .questions
+            .iter()
+            .zip([0, 10])
+            .map(|(question, opened_after)| ArchivedQuestion {
+                question: question.clone(),
+                opened_after: Duration::from_secs(opened_after),
+            })
+            .collect();
+        let answer = |player_id: &str, opened_after: u64, answer_time: u64| ArchivedAnswer {
+            player_id: player_id.into(),
+            correct: true,
+            answer_time: Duration::from_millis(answer_time),
+            answered_after: Duration::from_secs(opened_after) + Duration::from_millis(answer_time),
+        };
+        // The first two answers in the same leaderboard, the last one alone
+        game.answers = vec![
+            answer("alice", 0, 2000),
+            answer("bob", 0, 2300),
+            answer("alice", 10, 4000),
+        ];
+        game
+    }
+
+    // Every event with the number of milliseconds since the start of the replay
+    async fn replay(speed: f64) -> Vec<(String, u128)> {
+        let started_at = Instant::now();
+        past_game()
+            .replay(speed, LEADERBOARD_INTERVAL)
+            .map(|event| {
+                let event = match event {
+                    ReplayEvent::Question(question) => format!("question {}", *question.id),
+                    ReplayEvent::Leaderboard(leaderboard) => {
+                        let players: Vec<String> = leaderboard
+                            .list
+                            .iter()
+                            .map(|player| format!("{} {}", *player.id, player.points))
+                            .collect();
+                        format!("leaderboard {}", players.join(", "))
+                    }
+                };
+                (event, started_at.elapsed().as_millis())
+            })
+            .collect()
+            .await
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn games_are_replayed_with_their_timing() {
+        assert_eq!(
+            replay(1.0).await,
+            [
+                ("question 0".to_string(), 0),
+                ("leaderboard alice 1, bob 1".to_string(), 2500),
+                ("question 1".to_string(), 10000),
+                ("leaderboard alice 2, bob 1".to_string(), 14500),
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn faster_replays_keep_the_same_leaderboards() {
+        assert_eq!(
+            replay(2.0).await,
+            [
+                ("question 0".to_string(), 0),
+                ("leaderboard alice 1, bob 1".to_string(), 1250),
+                ("question 1".to_string(), 5000),
+                ("leaderboard alice 2, bob 1".to_string(), 7250),
+            ]
+        );
+    }
+}
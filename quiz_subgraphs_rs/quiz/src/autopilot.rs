@@ -0,0 +1,539 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_graphql::ID;
+use tokio::sync::{Mutex, Notify, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::models::{
+    open_question, InMemoryBroker, InMemoryDb, Question, QuestionClosed, QuestionSkipped,
+    QuizEvent, QuizPaused, QuizResumed,
+};
+use crate::question_bank::GameOptionsInput;
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct AutoPilotSettings {
+    // Time to answer a question without its own time limit
+    pub(crate) question_time: Duration,
+    // Time between a question being closed and the next one
+    pub(crate) results_time: Duration,
+}
+
+impl AutoPilotSettings {
+    fn time_limit(&self, question: &Question) -> Duration {
+        question
+            .time_limit
+            .map(|secs| Duration::from_secs(secs.into()))
+            .unwrap_or(self.question_time)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Phase {
+    // The current question is opened
+    Answering,
+    // The current question is closed, players look at the results
+    ShowingResults,
+}
+
+/// Where the auto-pilot is in a game, and the time left before moving on
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Step {
+    phase: Phase,
+    remaining: Duration,
+}
+
+impl Step {
+    /// Step to start from for a quiz in this state
+    pub(crate) fn starting(
+        settings: &AutoPilotSettings,
+        current_question: Option<&Question>,
+        question_opened: bool,
+    ) -> Self {
+        match current_question {
+            Some(question) if question_opened => Step {
+                phase: Phase::Answering,
+                remaining: settings.time_limit(question),
+            },
+            Some(_) => Step {
+                phase: Phase::ShowingResults,
+                remaining: settings.results_time,
+            },
+            // Open the first question right away
+            None => Step {
+                phase: Phase::ShowingResults,
+                remaining: Duration::ZERO,
+            },
+        }
+    }
+}
+
+struct AutoPilot {
+    settings: AutoPilotSettings,
+    // Notified on every answer to close the question once everyone answered
+    answered: Arc<Notify>,
+    // Notified to stop the task, which then returns the step it stopped at
+    pause: Arc<Notify>,
+    state: AutoPilotState,
+}
+
+enum AutoPilotState {
+    // The task returns `None` once the game is over
+    Running(JoinHandle<Option<Step>>),
+    // The task was told to stop and is being awaited
+    Stopping,
+    Paused(Step),
+}
+
+/// Games driven by the server: every question is opened, closed after its time limit (or once
+/// every player answered), and followed by the next one after the results were shown
+#[derive(Default)]
+pub(crate) struct AutoPilots {
+    // By quiz id
+    games: RwLock<HashMap<ID, AutoPilot>>,
+    // Held while starting, pausing, resuming or moving a game, so these don't interleave while
+    // a task is being stopped
+    changes: Mutex<()>,
+}
+
+impl AutoPilots {
+    /// Start from `step`, or wait for the game to be resumed if it's `paused`. Return false if
+    /// the auto-pilot is already on for this quiz
+    pub(crate) async fn start(
+        &self,
+        in_memory_db: &Arc<InMemoryDb>,
+        in_memory_broker: &Arc<InMemoryBroker>,
+        quiz_id: &ID,
+        settings: AutoPilotSettings,
+        step: Step,
+        paused: bool,
+    ) -> bool {
+        let _changes = self.changes.lock().await;
+        let mut games = self.games.write().await;
+        if let Some(game) = games.get(quiz_id) {
+            match &game.state {
+                AutoPilotState::Running(task) if task.is_finished() => {}
+                _ => return false,
+            }
+        }
+
+        let mut game = AutoPilot {
+            settings,
+            answered: Default::default(),
+            pause: Default::default(),
+            state: AutoPilotState::Paused(step),
+        };
+        if !paused {
+            game.restart(in_memory_db, in_memory_broker, quiz_id);
+        }
+        games.insert(quiz_id.clone(), game);
+        true
+    }
+
+    /// Pause the game and its auto-pilot (if any), return false if the game is not in progress
+    /// or already paused
+    pub(crate) async fn pause(
+        &self,
+        in_memory_db: &Arc<InMemoryDb>,
+        in_memory_broker: &Arc<InMemoryBroker>,
+        quiz_id: &ID,
+    ) -> bool {
+        let _changes = self.changes.lock().await;
+        let running = self.stop(quiz_id).await;
+        if !in_memory_db.pause(quiz_id).await {
+            let mut games = self.games.write().await;
+            if let Some(game) = games.get_mut(quiz_id).filter(|_| running) {
+                game.restart(in_memory_db, in_memory_broker, quiz_id);
+            }
+            return false;
+        }
+
+        in_memory_broker
+            .quiz_event(
+                quiz_id,
+                QuizEvent::QuizPaused(QuizPaused {
+                    quiz_id: quiz_id.clone(),
+                }),
+            )
+            .await;
+        true
+    }
+
+    /// Resume the game, its auto-pilot (if any) goes on from where it was paused. Return false
+    /// if the game wasn't paused
+    pub(crate) async fn resume(
+        &self,
+        in_memory_db: &Arc<InMemoryDb>,
+        in_memory_broker: &Arc<InMemoryBroker>,
+        quiz_id: &ID,
+    ) -> bool {
+        let _changes = self.changes.lock().await;
+        if !in_memory_db.resume(quiz_id).await {
+            return false;
+        }
+        if let Some(game) = self.games.write().await.get_mut(quiz_id) {
+            game.restart(in_memory_db, in_memory_broker, quiz_id);
+        }
+
+        in_memory_broker
+            .quiz_event(
+                quiz_id,
+                QuizEvent::QuizResumed(QuizResumed {
+                    quiz_id: quiz_id.clone(),
+                }),
+            )
+            .await;
+        true
+    }
+
+    /// Move to the question at `index` or the next one, starting the game with `options` if
+    /// needed. The auto-pilot (if any) then starts counting from it
+    pub(crate) async fn open_question(
+        &self,
+        in_memory_db: &Arc<InMemoryDb>,
+        in_memory_broker: &Arc<InMemoryBroker>,
+        quiz_id: &ID,
+        index: Option<usize>,
+        options: Option<GameOptionsInput>,
+    ) -> async_graphql::Result<Option<Question>> {
+        let _changes = self.changes.lock().await;
+        let running = self.stop(quiz_id).await;
+        let question = open_question(in_memory_db, in_memory_broker, quiz_id, index, options).await;
+        let mut games = self.games.write().await;
+        let Some(game) = games.get_mut(quiz_id) else {
+            return question;
+        };
+        match &question {
+            // Nothing changed, go on as before
+            Err(_) => {
+                if running {
+                    game.restart(in_memory_db, in_memory_broker, quiz_id);
+                }
+            }
+            Ok(Some(question)) => {
+                game.state = AutoPilotState::Paused(Step {
+                    phase: Phase::Answering,
+                    remaining: game.settings.time_limit(question),
+                });
+                if running {
+                    game.restart(in_memory_db, in_memory_broker, quiz_id);
+                }
+            }
+            Ok(None) => {
+                games.remove(quiz_id);
+            }
+        }
+
+        question
+    }
+
+    /// Skip the current question, the auto-pilot (if any) then shows the results before moving on.
+    /// Return false if no question was opened
+    pub(crate) async fn skip_question(
+        &self,
+        in_memory_db: &Arc<InMemoryDb>,
+        in_memory_broker: &Arc<InMemoryBroker>,
+        quiz_id: &ID,
+    ) -> bool {
+        let _changes = self.changes.lock().await;
+        let running = self.stop(quiz_id).await;
+        let skipped = in_memory_db.skip_question(quiz_id).await;
+        if let Some(game) = self.games.write().await.get_mut(quiz_id) {
+            if skipped.is_some() {
+                game.state = AutoPilotState::Paused(Step {
+                    phase: Phase::ShowingResults,
+                    remaining: game.settings.results_time,
+                });
+            }
+            if running {
+                game.restart(in_memory_db, in_memory_broker, quiz_id);
+            }
+        }
+
+        let Some(question_id) = skipped else {
+            return false;
+        };
+        in_memory_broker
+            .quiz_event(
+                quiz_id,
+                QuizEvent::QuestionSkipped(QuestionSkipped { question_id }),
+            )
+            .await;
+        true
+    }
+
+    // Stop the task of a quiz, keeping where it stopped. Return whether it was running, the
+    // auto-pilot is removed if the game it was driving is over. The task is awaited without
+    // holding the lock of the games, which it may be waiting for
+    async fn stop(&self, quiz_id: &ID) -> bool {
+        let task = {
+            let mut games = self.games.write().await;
+            let Some(game) = games.get_mut(quiz_id) else {
+                return false;
+            };
+            if !matches!(game.state, AutoPilotState::Running(_)) {
+                return false;
+            }
+            game.pause.notify_one();
+            match std::mem::replace(&mut game.state, AutoPilotState::Stopping) {
+                AutoPilotState::Running(task) => task,
+                _ => unreachable!("the task is running"),
+            }
+        };
+        let step = task.await.ok().flatten();
+
+        let mut games = self.games.write().await;
+        match step {
+            Some(step) => {
+                if let Some(game) = games.get_mut(quiz_id) {
+                    game.state = AutoPilotState::Paused(step);
+                }
+                true
+            }
+            None => {
+                games.remove(quiz_id);
+                false
+            }
+        }
+    }
+
+    /// To call on every answer, so the question is closed as soon as everyone answered
+    pub(crate) async fn answered(&self, quiz_id: &ID) {
+        if let Some(game) = self.games.read().await.get(quiz_id) {
+            game.answered.notify_one();
+        }
+    }
+}
+
+impl AutoPilot {
+    // Run the task from where it was paused
+    fn restart(
+        &mut self,
+        in_memory_db: &Arc<InMemoryDb>,
+        in_memory_broker: &Arc<InMemoryBroker>,
+        quiz_id: &ID,
+    ) {
+        if let AutoPilotState::Paused(step) = self.state {
+            self.run(in_memory_db, in_memory_broker, quiz_id, step);
+        }
+    }
+
+    fn run(
+        &mut self,
+        in_memory_db: &Arc<InMemoryDb>,
+        in_memory_broker: &Arc<InMemoryBroker>,
+        quiz_id: &ID,
+        step: Step,
+    ) {
+        self.state = AutoPilotState::Running(tokio::spawn(drive(
+            in_memory_db.clone(),
+            in_memory_broker.clone(),
+            quiz_id.clone(),
+            self.settings,
+            step,
+            self.answered.clone(),
+            self.pause.clone(),
+        )));
+    }
+}
+
+// Drive the game until it's over (returning `None`) or paused (returning where it stopped)
+async fn drive(
+    in_memory_db: Arc<InMemoryDb>,
+    in_memory_broker: Arc<InMemoryBroker>,
+    quiz_id: ID,
+    settings: AutoPilotSettings,
+    mut step: Step,
+    answered: Arc<Notify>,
+    pause: Arc<Notify>,
+) -> Option<Step> {
+    loop {
+        let deadline = Instant::now() + step.remaining;
+        match step.phase {
+            Phase::Answering => {
+                loop {
+                    if in_memory_db.all_answered(&quiz_id).await {
+                        break;
+                    }
+                    tokio::select! {
+                        _ = tokio::time::sleep_until(deadline) => break,
+                        _ = answered.notified() => {}
+                        _ = pause.notified() => {
+                            return Some(Step {
+                                remaining: deadline.saturating_duration_since(Instant::now()),
+                                ..step
+                            });
+                        }
+                    }
+                }
+                if let Some(question_id) = in_memory_db.close_question(&quiz_id).await {
+                    in_memory_broker
+                        .quiz_event(
+                            &quiz_id,
+                            QuizEvent::QuestionClosed(QuestionClosed { question_id }),
+                        )
+                        .await;
+                }
+                step = Step {
+                    phase: Phase::ShowingResults,
+                    remaining: settings.results_time,
+                };
+            }
+            Phase::ShowingResults => {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(deadline) => {}
+                    _ = pause.notified() => {
+                        return Some(Step {
+                            remaining: deadline.saturating_duration_since(Instant::now()),
+                            ..step
+                        });
+                    }
+                }
+                // Only an index can be out of range
+                let question =
+                    open_question(&in_memory_db, &in_memory_broker, &quiz_id, None, None)
+                        .await
+                        .ok()
+                        .flatten()?;
+                step = Step {
+                    phase: Phase::Answering,
+                    remaining: settings.time_limit(&question),
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::stream::BoxStream;
+    use futures_util::StreamExt;
+
+    use super::*;
+    use crate::models::DEFAULT_LEADERBOARD_INTERVAL;
+
+    // Quiz "0" of `data.json`, with two questions without a time limit
+    const QUIZ_ID: &str = "0";
+
+    const SETTINGS: AutoPilotSettings = AutoPilotSettings {
+        question_time: Duration::from_secs(20),
+        results_time: Duration::from_secs(5),
+    };
+
+    struct Game {
+        in_memory_db: Arc<InMemoryDb>,
+        in_memory_broker: Arc<InMemoryBroker>,
+        auto_pilots: AutoPilots,
+        quiz_id: ID,
+        events: BoxStream<'static, QuizEvent>,
+        started_at: Instant,
+    }
+
+    impl Game {
+        // The game of quiz "0" driven by the auto-pilot from its first question
+        async fn start(players: &[&str]) -> Self {
+            let in_memory_db = Arc::new(InMemoryDb::default());
+            let in_memory_broker = Arc::new(InMemoryBroker::new(DEFAULT_LEADERBOARD_INTERVAL));
+            let quiz_id = ID::from(QUIZ_ID);
+            for player in players {
+                in_memory_db.join(&ID::from(*player), &quiz_id).await;
+            }
+            let events = in_memory_broker.subscribe_quiz(&quiz_id).await.boxed();
+
+            let auto_pilots = AutoPilots::default();
+            let step = Step::starting(&SETTINGS, None, false);
+            assert!(
+                auto_pilots
+                    .start(
+                        &in_memory_db,
+                        &in_memory_broker,
+                        &quiz_id,
+                        SETTINGS,
+                        step,
+                        false
+                    )
+                    .await
+            );
+            Self {
+                in_memory_db,
+                in_memory_broker,
+                auto_pilots,
+                quiz_id,
+                events,
+                started_at: Instant::now(),
+            }
+        }
+
+        // Next event with the number of seconds since the start of the game
+        async fn next(&mut self) -> Option<(String, u64)> {
+            let event = match self.events.next().await? {
+                QuizEvent::Question(question) => format!("opened {}", *question.id),
+                QuizEvent::QuestionClosed(closed) => format!("closed {}", *closed.question_id),
+                QuizEvent::QuizPaused(_) => "paused".to_string(),
+                QuizEvent::QuizResumed(_) => "resumed".to_string(),
+                event => panic!("unexpected event {event:?}"),
+            };
+            Some((event, self.started_at.elapsed().as_secs()))
+        }
+    }
+
+    fn event(event: &str, after: u64) -> Option<(String, u64)> {
+        Some((event.to_string(), after))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn questions_are_closed_after_their_time_limit() {
+        let mut game = Game::start(&[]).await;
+
+        assert_eq!(game.next().await, event("opened 0", 0));
+        assert_eq!(game.next().await, event("closed 0", 20));
+        assert_eq!(game.next().await, event("opened 1", 25));
+        assert_eq!(game.next().await, event("closed 1", 45));
+        // The game is over after the results of the last question
+        assert_eq!(game.next().await, None);
+        assert_eq!(game.started_at.elapsed().as_secs(), 50);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn questions_are_closed_once_everyone_answered() {
+        let mut game = Game::start(&["alice", "bob"]).await;
+        assert_eq!(game.next().await, event("opened 0", 0));
+
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        for player in ["alice", "bob"] {
+            game.in_memory_db
+                .answer(&ID::from(player), &game.quiz_id, &ID::from("0"), None)
+                .await
+                .unwrap();
+            game.auto_pilots.answered(&game.quiz_id).await;
+        }
+
+        assert_eq!(game.next().await, event("closed 0", 3));
+        assert_eq!(game.next().await, event("opened 1", 8));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paused_games_keep_the_time_left() {
+        let mut game = Game::start(&[]).await;
+        assert_eq!(game.next().await, event("opened 0", 0));
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert!(
+            game.auto_pilots
+                .pause(&game.in_memory_db, &game.in_memory_broker, &game.quiz_id)
+                .await
+        );
+        assert_eq!(game.next().await, event("paused", 5));
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert!(
+            game.auto_pilots
+                .resume(&game.in_memory_db, &game.in_memory_broker, &game.quiz_id)
+                .await
+        );
+
+        assert_eq!(game.next().await, event("resumed", 65));
+        assert_eq!(game.next().await, event("closed 0", 80));
+    }
+}
@@ -0,0 +1,332 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_graphql::ID;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::Quiz;
+use crate::question_bank::GameOptions;
+
+/// Every change made to a game, the state of the games is rebuilt by applying them in order
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub(crate) enum GameEvent {
+    QuizStarted {
+        quiz_id: ID,
+        session_id: ID,
+        // Missing from logs written before games had join codes
+        #[serde(default)]
+        join_code: Option<String>,
+        // Missing from logs written before games had options, their questions were in file order
+        #[serde(default)]
+        options: GameOptions,
+        at: DateTime<Utc>,
+    },
+    QuestionOpened {
+        quiz_id: ID,
+        session_id: ID,
+        question_id: ID,
+        at: DateTime<Utc>,
+    },
+    AnswerSubmitted {
+        quiz_id: ID,
+        session_id: ID,
+        player_id: ID,
+        question_id: ID,
+        choice_id: Option<ID>,
+        correct: bool,
+        // Time between the question being opened and the answer
+        answer_time: Duration,
+    },
+    QuestionClosed {
+        quiz_id: ID,
+        session_id: ID,
+        question_id: ID,
+        at: DateTime<Utc>,
+    },
+    QuizFinished {
+        quiz_id: ID,
+        session_id: ID,
+        at: DateTime<Utc>,
+    },
+    QuizPaused {
+        quiz_id: ID,
+        session_id: ID,
+        at: DateTime<Utc>,
+    },
+    QuizResumed {
+        quiz_id: ID,
+        session_id: ID,
+        at: DateTime<Utc>,
+    },
+    // Without session when the player joined before the game started
+    PlayerJoined {
+        quiz_id: ID,
+        session_id: Option<ID>,
+        player_id: ID,
+        at: DateTime<Utc>,
+    },
+    // Closes the question and cancels the points of its answers
+    QuestionSkipped {
+        quiz_id: ID,
+        session_id: ID,
+        question_id: ID,
+        at: DateTime<Utc>,
+    },
+    // A quiz added to the ones of the quiz file, like an imported one
+    QuizAdded {
+        quiz: Box<Quiz>,
+        at: DateTime<Utc>,
+    },
+}
+
+impl GameEvent {
+    pub(crate) fn quiz_id(&self) -> &ID {
+        match self {
+            GameEvent::QuizStarted { quiz_id, .. }
+            | GameEvent::QuestionOpened { quiz_id, .. }
+            | GameEvent::AnswerSubmitted { quiz_id, .. }
+            | GameEvent::QuestionClosed { quiz_id, .. }
+            | GameEvent::QuizFinished { quiz_id, .. }
+            | GameEvent::QuizPaused { quiz_id, .. }
+            | GameEvent::QuizResumed { quiz_id, .. }
+            | GameEvent::PlayerJoined { quiz_id, .. }
+            | GameEvent::QuestionSkipped { quiz_id, .. } => quiz_id,
+            GameEvent::QuizAdded { quiz, .. } => &quiz.id,
+        }
+    }
+
+    pub(crate) fn session_id(&self) -> Option<&ID> {
+        let session_id = match self {
+            GameEvent::QuizStarted { session_id, .. }
+            | GameEvent::QuestionOpened { session_id, .. }
+            | GameEvent::AnswerSubmitted { session_id, .. }
+            | GameEvent::QuestionClosed { session_id, .. }
+            | GameEvent::QuizFinished { session_id, .. }
+            | GameEvent::QuizPaused { session_id, .. }
+            | GameEvent::QuizResumed { session_id, .. }
+            | GameEvent::QuestionSkipped { session_id, .. } => session_id,
+            GameEvent::PlayerJoined { session_id, .. } => return session_id.as_ref(),
+            GameEvent::QuizAdded { .. } => return None,
+        };
+        Some(session_id)
+    }
+}
+
+/// Append-only log of game events, stored as newline-delimited JSON.
+///
+/// Without a file the events are only applied in memory.
+#[derive(Default)]
+pub(crate) struct EventLog {
+    file: Option<Mutex<File>>,
+}
+
+impl EventLog {
+    /// Open the log at `path`, creating it if needed, and return the events already in it.
+    ///
+    /// A truncated last line, left by a crash in the middle of a write, is dropped.
+    pub(crate) fn open(path: &Path) -> io::Result<(Self, Vec<GameEvent>)> {
+        let mut events = Vec::new();
+        let mut valid_len = 0;
+        if path.exists() {
+            let mut reader = BufReader::new(File::open(path)?);
+            // Raw bytes, a line cut in the middle of a character isn't UTF-8
+            let mut line = Vec::new();
+            while reader.read_until(b'\n', &mut line)? > 0 {
+                let is_last = !line.ends_with(b"\n") || reader.fill_buf()?.is_empty();
+                let content = line.strip_suffix(b"\n").unwrap_or(&line);
+                match serde_json::from_slice(content) {
+                    Ok(event) => {
+                        events.push(event);
+                        valid_len += content.len() as u64 + 1;
+                    }
+                    Err(_) if content.trim_ascii().is_empty() => {
+                        valid_len += content.len() as u64 + 1
+                    }
+                    Err(_) if is_last => {
+                        eprintln!("dropping the truncated last event of the game log");
+                    }
+                    Err(err) => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("invalid event in the game log: {err}"),
+                        ))
+                    }
+                }
+                line.clear();
+            }
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .read(true)
+            .open(path)?;
+        // Don't append new events after a truncated one
+        let len = file.metadata()?.len();
+        if len > valid_len {
+            file.set_len(valid_len)?;
+        } else if len < valid_len {
+            // The last event was written but not its line break
+            file.write_all(b"\n")?;
+        }
+
+        Ok((
+            Self {
+                file: Some(Mutex::new(file)),
+            },
+            events,
+        ))
+    }
+
+    pub(crate) fn append(&self, event: &GameEvent) -> io::Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+        // A single write so a crash can only truncate the last line
+        file.lock()
+            .expect("the game log lock is poisoned")
+            .write_all(&line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::models::InMemoryDb;
+
+    /// A log file in the temporary directory, removed at the end of the test
+    struct TempLog(PathBuf);
+
+    impl TempLog {
+        fn new() -> Self {
+            Self(std::env::temp_dir().join(format!("game-log-{}.jsonl", Uuid::new_v4())))
+        }
+
+        fn write(&self, content: &[u8]) {
+            std::fs::write(&self.0, content).unwrap();
+        }
+
+        fn read(&self) -> Vec<u8> {
+            std::fs::read(&self.0).unwrap()
+        }
+    }
+
+    impl Drop for TempLog {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn paused(quiz_id: &str) -> GameEvent {
+        GameEvent::QuizPaused {
+            quiz_id: quiz_id.into(),
+            session_id: "session".into(),
+            at: DateTime::UNIX_EPOCH,
+        }
+    }
+
+    fn line(event: &GameEvent) -> Vec<u8> {
+        let mut line = serde_json::to_vec(event).unwrap();
+        line.push(b'\n');
+        line
+    }
+
+    fn quiz_ids(events: &[GameEvent]) -> Vec<&str> {
+        events
+            .iter()
+            .map(|event| event.quiz_id().as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn games_are_rebuilt_from_the_log() {
+        let log = TempLog::new();
+        let (quiz_id, player_id) = (ID::from("0"), ID::from("alice"));
+        let db = InMemoryDb::default().with_event_log(&log.0).unwrap();
+        let question = db.next_question(&quiz_id).await.unwrap();
+        db.answer(
+            &player_id,
+            &quiz_id,
+            &question.id,
+            Some(&question.good_answer),
+        )
+        .await
+        .unwrap();
+        drop(db);
+
+        let db = InMemoryDb::default().with_event_log(&log.0).unwrap();
+        let player = db.get_player(&player_id, &quiz_id).await.unwrap();
+        assert_eq!(player.points, 1);
+        // The game goes on where it was
+        let question = db.next_question(&quiz_id).await.unwrap();
+        assert_eq!(question.id, "1");
+    }
+
+    #[test]
+    fn truncated_last_lines_are_dropped() {
+        let log = TempLog::new();
+        let complete = [line(&paused("0")), line(&paused("1"))].concat();
+        let truncated = &line(&paused("2"))[..20];
+        log.write(&[&complete[..], truncated].concat());
+
+        let (event_log, events) = EventLog::open(&log.0).unwrap();
+        assert_eq!(quiz_ids(&events), ["0", "1"]);
+        assert_eq!(log.read(), complete);
+
+        event_log.append(&paused("3")).unwrap();
+        let (_, events) = EventLog::open(&log.0).unwrap();
+        assert_eq!(quiz_ids(&events), ["0", "1", "3"]);
+    }
+
+    #[test]
+    fn last_lines_cut_in_a_character_are_dropped() {
+        let log = TempLog::new();
+        let complete = line(&paused("0"));
+        let cut = line(&paused("é"));
+        // Up to the first byte of the two bytes of "é"
+        let cut_at = cut.iter().position(|byte| *byte == 0xc3).unwrap() + 1;
+        log.write(&[&complete[..], &cut[..cut_at]].concat());
+
+        let (_, events) = EventLog::open(&log.0).unwrap();
+        assert_eq!(quiz_ids(&events), ["0"]);
+        assert_eq!(log.read(), complete);
+    }
+
+    #[test]
+    fn events_are_appended_after_a_last_line_without_line_break() {
+        let log = TempLog::new();
+        let complete = [line(&paused("0")), line(&paused("1"))].concat();
+        log.write(&complete[..complete.len() - 1]);
+
+        let (event_log, events) = EventLog::open(&log.0).unwrap();
+        assert_eq!(quiz_ids(&events), ["0", "1"]);
+        event_log.append(&paused("2")).unwrap();
+
+        let (_, events) = EventLog::open(&log.0).unwrap();
+        assert_eq!(quiz_ids(&events), ["0", "1", "2"]);
+    }
+
+    #[test]
+    fn invalid_events_before_the_last_line_are_errors() {
+        let log = TempLog::new();
+        log.write(&[b"not an event\n".to_vec(), line(&paused("0"))].concat());
+
+        let err = EventLog::open(&log.0).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
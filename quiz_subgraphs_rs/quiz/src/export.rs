@@ -0,0 +1,106 @@
+use std::fmt::Write;
+
+use async_graphql::Enum;
+
+use crate::models::Quiz;
+
+#[derive(Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum QuizExportFormat {
+    /// The quiz as in `data.json`, with every question and translation
+    Json,
+    /// Printable answer key
+    Markdown,
+}
+
+impl QuizExportFormat {
+    /// From the extension of the exported file
+    pub(crate) fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "json" => Some(Self::Json),
+            "md" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Markdown => "text/markdown",
+        }
+    }
+}
+
+/// Export the questions of a quiz, not the state of its games
+pub(crate) fn export_quiz(quiz: &Quiz, format: QuizExportFormat) -> serde_json::Result<String> {
+    let quiz = quiz.definition();
+    match format {
+        QuizExportFormat::Json => serde_json::to_string_pretty(&quiz),
+        QuizExportFormat::Markdown => Ok(answer_key(&quiz)),
+    }
+}
+
+// The right choice of each question is checked, in the default language
+fn answer_key(quiz: &Quiz) -> String {
+    let mut markdown = format!("# {}\n", quiz.title.default_text());
+    for (index, question) in quiz.questions.iter().enumerate() {
+        // Writing to a `String` can't fail
+        let _ = write!(
+            markdown,
+            "\n## {}. {}\n\n",
+            index + 1,
+            question.title.default_text()
+        );
+        for choice in &question.choices {
+            let checked = if choice.id == question.good_answer {
+                "x"
+            } else {
+                " "
+            };
+            let _ = writeln!(markdown, "- [{checked}] {}", choice.text.default_text());
+        }
+        if let Some(time_limit) = question.time_limit {
+            let _ = write!(markdown, "\nTime limit: {time_limit} seconds\n");
+        }
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::import::import_quiz;
+    use crate::models::DATA_FILE;
+    use crate::quiz_file::{check, QuizFile};
+
+    fn reimport(quiz: &Quiz) -> Quiz {
+        let exported = export_quiz(quiz, QuizExportFormat::Json).unwrap();
+        let mut quizzes = QuizFile::parse(&exported).unwrap().into_quizzes();
+        assert_eq!(check(&quizzes), Vec::<String>::new());
+        assert_eq!(quizzes.len(), 1);
+        quizzes.remove(0)
+    }
+
+    fn to_value(quiz: &Quiz) -> Value {
+        serde_json::to_value(quiz).unwrap()
+    }
+
+    #[test]
+    fn exported_quizzes_are_imported_unchanged() {
+        let quizzes = QuizFile::parse(DATA_FILE).unwrap().into_quizzes();
+        for quiz in quizzes {
+            let quiz = quiz.with_bank();
+            assert_eq!(to_value(&reimport(&quiz)), to_value(&quiz));
+        }
+    }
+
+    #[test]
+    fn exported_spreadsheets_are_imported_unchanged() {
+        let csv = "Question,Answer 1,Answer 2,Answer 3,Answer 4,Time limit (sec),Correct answer\n\
+                   Capital of France?,Paris,Lyon,,,30,1\n\
+                   Capital of Italy?,Milan,Rome,Turin,,,2";
+        let quiz = import_quiz("capitals.csv", csv.as_bytes(), None).unwrap();
+        assert_eq!(to_value(&reimport(&quiz)), to_value(&quiz));
+    }
+}
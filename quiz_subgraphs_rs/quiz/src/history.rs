@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_graphql::{SimpleObject, ID};
+
+use crate::models::Badge;
+
+// Number of right answers in a row to unlock `Badge::FiveInARow`
+const STREAK_BADGE_LENGTH: usize = 5;
+
+#[derive(Clone, Debug)]
+pub(crate) struct AnswerRecord {
+    pub(crate) question_id: ID,
+    pub(crate) correct: bool,
+    // Time between the question being opened and the answer
+    pub(crate) answer_time: Duration,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct AskedQuestion {
+    pub(crate) id: ID,
+    // Time between the game start and the question being opened
+    pub(crate) opened_after: Duration,
+}
+
+/// Every answer given during a game, by player
+#[derive(Default, Debug)]
+pub(crate) struct GameHistory {
+    // Questions in the order they were asked
+    questions: Vec<AskedQuestion>,
+    players: HashMap<ID, PlayerHistory>,
+    // Player who gave the first right answer, by question id
+    fastest_fingers: HashMap<ID, ID>,
+    // Number of players who answered, by question id, to follow a question without going
+    // through every answer
+    answered: HashMap<ID, usize>,
+}
+
+#[derive(Default, Debug)]
+pub(crate) struct PlayerHistory {
+    pub(crate) answers: Vec<AnswerRecord>,
+    pub(crate) badges: Vec<Badge>,
+}
+
+/// How a player did so far in the current or last game of a quiz
+#[derive(Default, Debug, SimpleObject)]
+pub(crate) struct PlayerStats {
+    /// Number of right answers in a row up to the last answer
+    pub(crate) current_streak: usize,
+    pub(crate) longest_streak: usize,
+    /// Share of right answers, between 0 and 1. Null until the player answered a question
+    pub(crate) accuracy: Option<f64>,
+    /// Average time to answer a question in milliseconds. Null until the player answered a question
+    pub(crate) average_response_time: Option<u64>,
+    /// Badges unlocked during the quiz, in the order they were unlocked
+    #[graphql(name = "achievements")]
+    pub(crate) badges: Vec<Badge>,
+}
+
+impl GameHistory {
+    /// A question asked again moves to the end, opened at its new time
+    pub(crate) fn open_question(&mut self, question_id: &ID, opened_after: Duration) {
+        self.questions.retain(|asked| &asked.id != question_id);
+        self.questions.push(AskedQuestion {
+            id: question_id.clone(),
+            opened_after,
+        });
+    }
+
+    pub(crate) fn questions(&self) -> &[AskedQuestion] {
+        &self.questions
+    }
+
+    /// Every answer with the player who gave it
+    pub(crate) fn answers(&self) -> impl Iterator<Item = (&ID, &AnswerRecord)> {
+        self.players.iter().flat_map(|(player_id, player)| {
+            player.answers.iter().map(move |answer| (player_id, answer))
+        })
+    }
+
+    /// Answers of every player to a question
+    pub(crate) fn answers_to<'a>(
+        &'a self,
+        question_id: &'a ID,
+    ) -> impl Iterator<Item = &'a AnswerRecord> {
+        self.players
+            .values()
+            .flat_map(|player| player.answers.iter())
+            .filter(move |answer| &answer.question_id == question_id)
+    }
+
+    /// Record an answer and return the badges it unlocked
+    pub(crate) fn record(&mut self, player_id: &ID, answer: AnswerRecord) -> Vec<Badge> {
+        let mut unlocked = Vec::new();
+        if answer.correct && !self.fastest_fingers.contains_key(&answer.question_id) {
+            self.fastest_fingers
+                .insert(answer.question_id.clone(), player_id.clone());
+            unlocked.push(Badge::FastestFinger);
+        }
+
+        *self.answered.entry(answer.question_id.clone()).or_default() += 1;
+        let player = self.players.entry(player_id.clone()).or_default();
+        player.answers.push(answer);
+        if player.current_streak() == STREAK_BADGE_LENGTH
+            && !player.badges.contains(&Badge::FiveInARow)
+        {
+            unlocked.push(Badge::FiveInARow);
+        }
+        player.badges.extend_from_slice(&unlocked);
+
+        unlocked
+    }
+
+    /// Remove the answers to a question and return them, for a question skipped by the host.
+    /// Badges they unlocked are kept
+    pub(crate) fn void(&mut self, question_id: &ID) -> Vec<(ID, AnswerRecord)> {
+        self.answered.remove(question_id);
+        let mut voided = Vec::new();
+        for (player_id, player) in &mut self.players {
+            let (removed, kept) = std::mem::take(&mut player.answers)
+                .into_iter()
+                .partition(|answer| &answer.question_id == question_id);
+            player.answers = kept;
+            voided.extend(
+                removed
+                    .into_iter()
+                    .map(|answer: AnswerRecord| (player_id.clone(), answer)),
+            );
+        }
+        voided
+    }
+
+    pub(crate) fn has_answered(&self, player_id: &ID, question_id: &ID) -> bool {
+        self.players
+            .get(player_id)
+            .is_some_and(|player| player.answers.iter().any(|a| &a.question_id == question_id))
+    }
+
+    /// Number of players who answered a question
+    pub(crate) fn answered_count(&self, question_id: &ID) -> usize {
+        self.answered.get(question_id).copied().unwrap_or_default()
+    }
+
+    pub(crate) fn player(&self, player_id: &ID) -> Option<&PlayerHistory> {
+        self.players.get(player_id)
+    }
+}
+
+impl PlayerHistory {
+    pub(crate) fn stats(&self) -> PlayerStats {
+        let right_answers = self.answers.iter().filter(|a| a.correct).count();
+        let longest_streak = self
+            .answers
+            .split(|a| !a.correct)
+            .map(|streak| streak.len())
+            .max()
+            .unwrap_or_default();
+        let total_time: Duration = self.answers.iter().map(|a| a.answer_time).sum();
+
+        PlayerStats {
+            current_streak: self.current_streak(),
+            longest_streak,
+            accuracy: (!self.answers.is_empty())
+                .then(|| right_answers as f64 / self.answers.len() as f64),
+            average_response_time: (!self.answers.is_empty())
+                .then(|| (total_time / self.answers.len() as u32).as_millis() as u64),
+            badges: self.badges.clone(),
+        }
+    }
+
+    fn current_streak(&self) -> usize {
+        self.answers.iter().rev().take_while(|a| a.correct).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn right_answer(question_id: usize) -> AnswerRecord {
+        AnswerRecord {
+            question_id: ID::from(question_id.to_string()),
+            correct: true,
+            answer_time: Duration::from_secs(1),
+        }
+    }
+
+    #[test]
+    fn five_in_a_row_is_awarded_once() {
+        let mut history = GameHistory::default();
+        let player_id = ID::from("alice");
+        let unlocked: Vec<Badge> = (0..10)
+            .flat_map(|question| history.record(&player_id, right_answer(question)))
+            .filter(|badge| badge == &Badge::FiveInARow)
+            .collect();
+
+        assert_eq!(unlocked, [Badge::FiveInARow]);
+        assert_eq!(
+            history.player(&player_id).unwrap().stats().current_streak,
+            10
+        );
+    }
+
+    #[test]
+    fn answered_count_follows_records_and_voids() {
+        let mut history = GameHistory::default();
+        let question_id = ID::from("0");
+        for player_id in ["alice", "bob"] {
+            history.record(&ID::from(player_id), right_answer(0));
+        }
+        history.record(&ID::from("alice"), right_answer(1));
+        assert_eq!(history.answered_count(&question_id), 2);
+        assert_eq!(history.answered_count(&ID::from("1")), 1);
+
+        history.void(&question_id);
+        assert_eq!(history.answered_count(&question_id), 0);
+        assert_eq!(history.answered_count(&ID::from("1")), 1);
+    }
+}
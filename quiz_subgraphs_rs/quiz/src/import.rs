@@ -0,0 +1,315 @@
+use std::io::Cursor;
+use std::path::Path;
+
+use async_graphql::ID;
+use calamine::{open_workbook_auto_from_rs, Reader};
+use uuid::Uuid;
+
+use crate::locale::LocalizedText;
+use crate::models::{Choice, Question, Quiz};
+
+// Like on Kahoot
+const MAX_ANSWERS: usize = 4;
+
+// Columns of the spreadsheet, found with the start of their header
+struct Columns {
+    question: usize,
+    answers: Vec<usize>,
+    time_limit: Option<usize>,
+    correct_answer: usize,
+}
+
+impl Columns {
+    // The header is the first row with a "Question" column, the Kahoot template has a few rows
+    // of explanations above it
+    fn find(rows: &[Vec<String>]) -> Result<(usize, Self), String> {
+        let (header_index, header) = rows
+            .iter()
+            .enumerate()
+            .find(|(_, row)| row.iter().any(|cell| starts_with(cell, "question")))
+            .ok_or("cannot find the header row with a \"Question\" column")?;
+        let column = |name: &str| header.iter().position(|cell| starts_with(cell, name));
+        let missing = |name: &str| format!("cannot find the \"{name}\" column");
+
+        let answers: Vec<usize> = (1..=MAX_ANSWERS)
+            .map_while(|i| column(&format!("answer {i}")))
+            .collect();
+        if answers.len() < 2 {
+            return Err(missing(&format!("Answer {}", answers.len() + 1)));
+        }
+        let columns = Self {
+            question: column("question").ok_or_else(|| missing("Question"))?,
+            answers,
+            time_limit: column("time limit"),
+            correct_answer: column("correct").ok_or_else(|| missing("Correct answer"))?,
+        };
+        Ok((header_index, columns))
+    }
+}
+
+fn starts_with(cell: &str, name: &str) -> bool {
+    cell.trim().to_lowercase().starts_with(name)
+}
+
+/// Build a quiz from a spreadsheet with one question by row and the columns of the Kahoot
+/// template: "Question", "Answer 1" to "Answer 4", "Time limit (sec)" (optional) and "Correct
+/// answer" (from 1 to 4). `file_name` tells CSV files from XLSX, XLS and ODS ones
+pub(crate) fn import_quiz(
+    file_name: &str,
+    content: &[u8],
+    title: Option<String>,
+) -> Result<Quiz, String> {
+    let rows = if file_name.to_lowercase().ends_with(".csv") {
+        csv_rows(content)?
+    } else {
+        spreadsheet_rows(content)?
+    };
+    let (header_index, columns) = Columns::find(&rows)?;
+
+    let mut questions = Vec::new();
+    for (index, row) in rows.iter().enumerate().skip(header_index + 1) {
+        let cell = |column: usize| row.get(column).map(|cell| cell.trim()).unwrap_or_default();
+        // As seen in the spreadsheet, where the first row is 1
+        let row_number = index + 1;
+        if cell(columns.question).is_empty() {
+            continue;
+        }
+
+        let choices: Vec<Choice> = columns
+            .answers
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| !cell(**column).is_empty())
+            .map(|(i, column)| Choice {
+                id: ID::from(i.to_string()),
+                text: LocalizedText::Single(cell(*column).to_string()),
+            })
+            .collect();
+        if choices.len() < 2 {
+            return Err(format!(
+                "row {row_number}: a question needs at least two answers"
+            ));
+        }
+        let correct_answer = cell(columns.correct_answer);
+        if correct_answer.contains(',') {
+            return Err(format!(
+                "row {row_number}: only one correct answer by question is supported"
+            ));
+        }
+        let good_answer = correct_answer
+            .parse::<usize>()
+            .ok()
+            .and_then(|answer| answer.checked_sub(1))
+            .map(|answer| ID::from(answer.to_string()))
+            .filter(|answer| choices.iter().any(|choice| &choice.id == answer))
+            .ok_or_else(|| {
+                format!("row {row_number}: the correct answer must be the number of an answer")
+            })?;
+        let time_limit = match columns.time_limit.map(cell).filter(|cell| !cell.is_empty()) {
+            Some(time_limit) => Some(
+                time_limit
+                    .parse()
+                    .map_err(|_| format!("row {row_number}: invalid time limit {time_limit}"))?,
+            ),
+            None => None,
+        };
+
+        questions.push(Question {
+            id: ID::from(questions.len().to_string()),
+            title: LocalizedText::Single(cell(columns.question).to_string()),
+            choices,
+            good_answer,
+            time_limit,
+            choice_seed: None,
+        });
+    }
+    if questions.is_empty() {
+        return Err("the spreadsheet has no question".to_string());
+    }
+
+    let title = title.unwrap_or_else(|| {
+        Path::new(file_name)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    });
+    Ok(Quiz {
+        id: ID::from(Uuid::new_v4().to_string()),
+        title: LocalizedText::Single(title),
+        bank: questions.clone(),
+        questions,
+        ..Default::default()
+    })
+}
+
+/// Convert a spreadsheet to a quiz in the format of `data.json`, for the `import` command
+pub fn import_quiz_file(path: &Path, title: Option<String>) -> Result<String, String> {
+    let content = std::fs::read(path).map_err(|err| format!("cannot read {path:?}: {err}"))?;
+    let quiz = import_quiz(&path.to_string_lossy(), &content, title)?;
+    serde_json::to_string_pretty(&quiz).map_err(|err| err.to_string())
+}
+
+fn csv_rows(content: &[u8]) -> Result<Vec<Vec<String>>, String> {
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(content)
+        .records()
+        .map(|record| {
+            record
+                .map(|record| record.iter().map(str::to_string).collect())
+                .map_err(|err| format!("invalid CSV: {err}"))
+        })
+        .collect()
+}
+
+// First sheet of an XLSX, XLS or ODS file
+fn spreadsheet_rows(content: &[u8]) -> Result<Vec<Vec<String>>, String> {
+    let mut workbook = open_workbook_auto_from_rs(Cursor::new(content))
+        .map_err(|err| format!("invalid spreadsheet: {err}"))?;
+    let sheet = workbook
+        .worksheet_range_at(0)
+        .ok_or("the spreadsheet has no sheet")?
+        .map_err(|err| format!("invalid spreadsheet: {err}"))?;
+    Ok(sheet
+        .rows()
+        .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    const HEADER: &str =
+        "Question,Answer 1,Answer 2,Answer 3,Answer 4,Time limit (sec),Correct answer";
+
+    fn questions(quiz: &Quiz) -> Value {
+        serde_json::to_value(&quiz.questions).unwrap()
+    }
+
+    fn import_csv(rows: &str) -> Result<Quiz, String> {
+        import_quiz("quiz.csv", format!("{HEADER}\n{rows}").as_bytes(), None)
+    }
+
+    #[test]
+    fn kahoot_template() {
+        let quiz = import_quiz(
+            "kahoot.csv",
+            include_bytes!("../tests/fixtures/kahoot.csv"),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(serde_json::to_value(&quiz.title).unwrap(), "kahoot");
+        assert_eq!(
+            questions(&quiz),
+            json!([
+                {
+                    "id": "0",
+                    "title": "What is the capital of France?",
+                    "choices": [{ "id": "0", "text": "Berlin" }, { "id": "1", "text": "Paris" }],
+                    "good_answer": "1",
+                    "time_limit": 20,
+                },
+                {
+                    "id": "1",
+                    "title": "How many subscription modes are there?",
+                    "choices": [
+                        { "id": "0", "text": "1" },
+                        { "id": "1", "text": "2" },
+                        { "id": "2", "text": "3" },
+                        { "id": "3", "text": "4" },
+                    ],
+                    "good_answer": "0",
+                },
+            ])
+        );
+    }
+
+    // Number cells are floats in spreadsheets, "1" and "20" are read as 1.0 and 20.0
+    #[test]
+    fn kahoot_template_as_xlsx() {
+        let quiz = import_quiz(
+            "kahoot.xlsx",
+            include_bytes!("../tests/fixtures/kahoot.xlsx"),
+            Some("Capitals".to_string()),
+        )
+        .unwrap();
+
+        assert_eq!(serde_json::to_value(&quiz.title).unwrap(), "Capitals");
+        let questions = questions(&quiz);
+        assert_eq!(questions[0]["good_answer"], "1");
+        assert_eq!(questions[0]["time_limit"], 20);
+        assert_eq!(questions[1]["choices"][0]["text"], "1");
+        assert_eq!(questions[1]["good_answer"], "0");
+        assert_eq!(questions[1]["time_limit"], 30);
+    }
+
+    #[test]
+    fn header_is_needed() {
+        assert_eq!(
+            import_quiz(
+                "quiz.csv",
+                b"Title,Answer 1\nCapital of France?,Paris",
+                None
+            )
+            .unwrap_err(),
+            "cannot find the header row with a \"Question\" column"
+        );
+    }
+
+    #[test]
+    fn columns_are_needed() {
+        assert_eq!(
+            import_quiz("quiz.csv", b"Question,Answer 1,Correct answer", None).unwrap_err(),
+            "cannot find the \"Answer 2\" column"
+        );
+        assert_eq!(
+            import_quiz("quiz.csv", b"Question,Answer 1,Answer 2", None).unwrap_err(),
+            "cannot find the \"Correct answer\" column"
+        );
+    }
+
+    #[test]
+    fn questions_need_two_answers() {
+        assert_eq!(
+            import_csv("Capital of France?,Paris,,,,,1").unwrap_err(),
+            "row 2: a question needs at least two answers"
+        );
+    }
+
+    #[test]
+    fn questions_have_one_correct_answer() {
+        assert_eq!(
+            import_csv("Capital of France?,Berlin,Paris,,,,\"1,2\"").unwrap_err(),
+            "row 2: only one correct answer by question is supported"
+        );
+        assert_eq!(
+            import_csv("Capital of France?,Berlin,Paris,,,,3").unwrap_err(),
+            "row 2: the correct answer must be the number of an answer"
+        );
+        assert_eq!(
+            import_csv("Capital of France?,Berlin,Paris,,,,").unwrap_err(),
+            "row 2: the correct answer must be the number of an answer"
+        );
+    }
+
+    #[test]
+    fn time_limits_are_seconds() {
+        assert_eq!(
+            import_csv("Capital of France?,Berlin,Paris,,,ten,2").unwrap_err(),
+            "row 2: invalid time limit ten"
+        );
+    }
+
+    #[test]
+    fn quizzes_have_questions() {
+        assert_eq!(
+            import_csv(",Berlin,Paris,,,,2").unwrap_err(),
+            "the spreadsheet has no question"
+        );
+    }
+}
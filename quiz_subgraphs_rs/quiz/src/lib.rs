@@ -0,0 +1,165 @@
+mod archive;
+mod autopilot;
+mod event_log;
+mod export;
+mod history;
+mod import;
+mod locale;
+mod models;
+mod question_bank;
+mod quiz_file;
+mod ranking;
+mod report;
+
+use async_graphql::{Data, Schema, ID};
+use axum::extract::Path;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Extension, Router};
+use http::{header, HeaderMap, StatusCode};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use subgraph_core::RouterBuilder;
+
+use autopilot::AutoPilots;
+use export::{export_quiz, QuizExportFormat};
+use locale::AcceptLanguage;
+use models::{MutationRoot, QueryRoot, SubscriptionRoot};
+
+pub use import::import_quiz_file;
+pub use models::{InMemoryBroker, InMemoryDb, DEFAULT_LEADERBOARD_INTERVAL};
+pub use quiz_file::{quiz_file_json_schema, seed_quiz_file, validate_quiz_file};
+
+/// Requests get the player like in every subgraph, and the languages from the `locale` field
+/// of the `connection_init` payload for subscriptions or from the `Accept-Language` header
+fn request_data(headers: &HeaderMap, payload: &Value) -> Data {
+    let mut data = subgraph_core::player_data(headers, payload);
+    if let Some(accept_language) = payload["locale"]
+        .as_str()
+        .map(AcceptLanguage::parse)
+        .or_else(|| AcceptLanguage::from_headers(headers))
+    {
+        data.insert(accept_language);
+    }
+    data
+}
+
+/// Export the report of a game session, `format` being `json` or `csv`
+async fn game_report_export(
+    Extension(in_memory_db): Extension<Arc<InMemoryDb>>,
+    Path((session_id, format)): Path<(ID, String)>,
+) -> Response {
+    let Some(report) = in_memory_db.game_report(&session_id).await else {
+        return (StatusCode::NOT_FOUND, "game session not found").into_response();
+    };
+    let export = match format.as_str() {
+        "json" => serde_json::to_string(&report).map_err(|err| err.to_string()),
+        "csv" => report.to_csv().map_err(|err| err.to_string()),
+        _ => return (StatusCode::NOT_FOUND, "unknown format, use json or csv").into_response(),
+    };
+
+    match export {
+        Ok(export) => (
+            [
+                (
+                    header::CONTENT_TYPE,
+                    if format == "json" {
+                        "application/json"
+                    } else {
+                        "text/csv"
+                    }
+                    .to_string(),
+                ),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"report-{}.{format}\"", *session_id),
+                ),
+            ],
+            export,
+        )
+            .into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err).into_response(),
+    }
+}
+
+/// Export the questions of a quiz, `format` being `json` (like `data.json`) or `md` (answer key)
+async fn quiz_export(
+    Extension(in_memory_db): Extension<Arc<InMemoryDb>>,
+    Path((quiz_id, format)): Path<(ID, String)>,
+) -> Response {
+    let Some(quiz) = in_memory_db.get_quiz(&quiz_id).await else {
+        return (StatusCode::NOT_FOUND, "quiz not found").into_response();
+    };
+    let Some(export_format) = QuizExportFormat::from_extension(&format) else {
+        return (StatusCode::NOT_FOUND, "unknown format, use json or md").into_response();
+    };
+
+    match export_quiz(&quiz, export_format) {
+        Ok(export) => (
+            [
+                (
+                    header::CONTENT_TYPE,
+                    export_format.content_type().to_string(),
+                ),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"quiz-{}.{format}\"", *quiz_id),
+                ),
+            ],
+            export,
+        )
+            .into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+fn leaderboard_interval() -> Result<Duration, String> {
+    match std::env::var("LEADERBOARD_INTERVAL_MS") {
+        Ok(interval) => interval.parse().map(Duration::from_millis).map_err(|_| {
+            format!("LEADERBOARD_INTERVAL_MS must be a number of milliseconds, not {interval:?}")
+        }),
+        Err(_) => Ok(DEFAULT_LEADERBOARD_INTERVAL),
+    }
+}
+
+// Games are only kept in memory unless `GAME_LOG_PATH` is set
+fn in_memory_db(data: Option<&std::path::Path>) -> Result<InMemoryDb, String> {
+    let in_memory_db = match data {
+        Some(path) => InMemoryDb::with_quiz_file(path).map_err(|problems| problems.join("\n"))?,
+        None => InMemoryDb::default(),
+    };
+    match std::env::var("GAME_LOG_PATH") {
+        Ok(path) => in_memory_db
+            .with_event_log(&path)
+            .map_err(|err| format!("cannot load the game log {path}: {err}")),
+        Err(_) => Ok(in_memory_db),
+    }
+}
+
+fn schema() -> Schema<QueryRoot, MutationRoot, SubscriptionRoot> {
+    subgraph_core::schema_builder(QueryRoot, MutationRoot, SubscriptionRoot).finish()
+}
+
+/// SDL of the subgraph with its federation directives, as composed by the router
+pub fn schema_sdl() -> String {
+    subgraph_core::schema_sdl(&schema())
+}
+
+/// Serve the quizzes of the `data` quiz file, or the built-in ones
+pub fn app(data: Option<&std::path::Path>) -> Result<Router, String> {
+    let in_memory_db = Arc::new(in_memory_db(data)?);
+    let in_memory_broker = Arc::new(InMemoryBroker::new(leaderboard_interval()?));
+    let schema = subgraph_core::schema_builder(QueryRoot, MutationRoot, SubscriptionRoot)
+        .data(in_memory_db.clone())
+        .data(in_memory_broker)
+        .data(AutoPilots::default())
+        .finish();
+
+    Ok(RouterBuilder::new(schema)
+        .request_data(request_data)
+        .route("/reports/:session_id/:format", get(game_report_export))
+        .route("/quizzes/:quiz_id/:format", get(quiz_export))
+        .finish()
+        .layer(Extension(in_memory_db)))
+}
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+
+use async_graphql::Context;
+use http::HeaderMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Language of the quiz content when none of the languages asked for is available
+pub(crate) const DEFAULT_LANGUAGE: &str = "en";
+
+/// Languages asked for by the client, the preferred one first
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AcceptLanguage(Vec<String>);
+
+impl AcceptLanguage {
+    /// Parse an `Accept-Language` value like `fr-CH, fr;q=0.9, en;q=0.8, *;q=0.5`
+    pub(crate) fn parse(value: &str) -> Self {
+        let mut languages: Vec<(String, f32)> = value
+            .split(',')
+            .filter_map(|language| {
+                let mut parts = language.split(';');
+                let tag = parts.next()?.trim();
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |quality| quality.trim().parse().ok())?;
+                (!tag.is_empty() && tag != "*" && quality > 0.0)
+                    .then(|| (tag.to_lowercase(), quality))
+            })
+            .collect();
+        // Stable, so languages with the same quality keep their order
+        languages.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        Self(languages.into_iter().map(|(tag, _)| tag).collect())
+    }
+
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        headers
+            .get(http::header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .map(Self::parse)
+    }
+}
+
+/// Text of the quiz content, a single string or one string by language tag like `"en"` or
+/// `"fr"` in the quiz file
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub(crate) enum LocalizedText {
+    Single(String),
+    Translated(BTreeMap<String, String>),
+}
+
+impl Default for LocalizedText {
+    fn default() -> Self {
+        Self::Single(String::new())
+    }
+}
+
+impl LocalizedText {
+    /// Text in `locale`, or else in the languages of the `Accept-Language` header, or else in
+    /// the default language
+    pub(crate) fn resolve(&self, ctx: &Context<'_>, locale: Option<&str>) -> &str {
+        let accept_language = ctx.data_opt::<AcceptLanguage>();
+        let languages = locale.map(str::to_lowercase).into_iter().chain(
+            accept_language
+                .into_iter()
+                .flat_map(|languages| languages.0.clone()),
+        );
+        self.text(languages)
+    }
+
+    /// Text in the default language, for the exports of the host
+    pub(crate) fn default_text(&self) -> &str {
+        self.text(std::iter::empty())
+    }
+
+    fn text(&self, languages: impl IntoIterator<Item = String>) -> &str {
+        let translations = match self {
+            Self::Single(text) => return text,
+            Self::Translated(translations) => translations,
+        };
+        languages
+            .into_iter()
+            .chain(std::iter::once(DEFAULT_LANGUAGE.to_string()))
+            .find_map(|language| find_translation(translations, &language))
+            // Quizzes without the default language still show something
+            .or_else(|| translations.values().next())
+            .map_or("", String::as_str)
+    }
+}
+
+// Exact match first, then `fr` for `fr-ch` and `fr-ch` for `fr`
+fn find_translation<'a>(
+    translations: &'a BTreeMap<String, String>,
+    language: &str,
+) -> Option<&'a String> {
+    let primary = |tag: &str| tag.split('-').next().unwrap_or_default().to_lowercase();
+    translations
+        .iter()
+        .find(|(tag, _)| tag.eq_ignore_ascii_case(language))
+        .or_else(|| {
+            translations
+                .iter()
+                .find(|(tag, _)| primary(tag) == primary(language))
+        })
+        .map(|(_, text)| text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translated(translations: &[(&str, &str)]) -> LocalizedText {
+        LocalizedText::Translated(
+            translations
+                .iter()
+                .map(|(tag, text)| (tag.to_string(), text.to_string()))
+                .collect(),
+        )
+    }
+
+    fn text<'a>(text: &'a LocalizedText, accept_language: &str) -> &'a str {
+        text.text(AcceptLanguage::parse(accept_language).0)
+    }
+
+    #[test]
+    fn languages_are_sorted_by_quality() {
+        let languages = AcceptLanguage::parse("en;q=0.8, fr-CH, de;q=0.9, *;q=0.5, it;q=0");
+        assert_eq!(languages.0, ["fr-ch", "de", "en"]);
+    }
+
+    #[test]
+    fn languages_with_the_same_quality_keep_their_order() {
+        let languages = AcceptLanguage::parse("de;q=0.5, fr, en;q=0.5");
+        assert_eq!(languages.0, ["fr", "de", "en"]);
+    }
+
+    #[test]
+    fn malformed_qualities_are_ignored() {
+        let languages = AcceptLanguage::parse("fr;q=high, en");
+        assert_eq!(languages.0, ["en"]);
+    }
+
+    #[test]
+    fn regions_fall_back_to_their_language() {
+        let question = translated(&[("en", "Hello"), ("fr", "Bonjour")]);
+        assert_eq!(text(&question, "fr-CA"), "Bonjour");
+
+        let question = translated(&[("en", "Hello"), ("fr-CA", "Allô")]);
+        assert_eq!(text(&question, "fr"), "Allô");
+        assert_eq!(text(&question, "fr-ca"), "Allô");
+    }
+
+    #[test]
+    fn missing_translations_fall_back_to_the_default_language() {
+        let question = translated(&[("de", "Hallo"), ("en", "Hello")]);
+        assert_eq!(text(&question, "it, es;q=0.5"), "Hello");
+        assert_eq!(question.default_text(), "Hello");
+
+        // Without the default language, any translation is better than nothing
+        let question = translated(&[("de", "Hallo")]);
+        assert_eq!(text(&question, "it"), "Hallo");
+    }
+
+    #[test]
+    fn single_texts_are_the_same_in_every_language() {
+        let question = LocalizedText::Single(String::from("Hello"));
+        assert_eq!(text(&question, "fr"), "Hello");
+    }
+}
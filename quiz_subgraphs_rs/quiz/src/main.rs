@@ -1,79 +1,94 @@
-mod models;
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+use subgraph_core::ServerArgs;
 
-use async_graphql::http::GraphiQLSource;
-use async_graphql::{Schema, ID};
-use async_graphql_axum::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
-use axum::response::IntoResponse;
-use axum::routing::get;
-use axum::Server;
-use axum::{Extension, Router};
-use http::HeaderMap;
-use std::net::Ipv4Addr;
-use tower::ServiceBuilder;
-use tower_http::cors::CorsLayer;
+const DEFAULT_PORT: u16 = 4005;
 
-use models::{InMemoryBroker, InMemoryDb, MutationRoot, QueryRoot, SubscriptionRoot};
-
-fn get_player_id_from_headers(headers: &HeaderMap) -> Option<ID> {
-    headers
-        .get("player")
-        .and_then(|value| value.to_str().map(ID::from).ok())
-}
-
-async fn simple_graphql_handler(
-    schema: Extension<Schema<QueryRoot, MutationRoot, SubscriptionRoot>>,
-    headers: HeaderMap,
-    req: GraphQLRequest,
-) -> GraphQLResponse {
-    let mut req = req.into_inner();
-    if let Some(player_id) = get_player_id_from_headers(&headers) {
-        req = req.data(player_id);
-    }
-
-    schema.execute(req).await.into()
+#[derive(Parser)]
+#[command(
+    about = "Quiz subgraph, serves GraphQL without a command",
+    args_conflicts_with_subcommands = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    serve: ServeArgs,
 }
 
-async fn graphiql() -> impl IntoResponse {
-    axum::response::Html(
-        GraphiQLSource::build()
-            .endpoint("/")
-            .subscription_endpoint("/ws")
-            .finish(),
-    )
+#[derive(Subcommand)]
+enum Command {
+    /// Serve GraphQL, the default command
+    Serve(ServeArgs),
+    /// Print the SDL of the subgraph with its federation directives, for `rover supergraph compose`
+    PrintSchema,
+    /// Check a quiz file like `data.json`, reporting the mistakes its JSON Schema can't catch
+    #[command(alias = "validate")]
+    ValidateData { file: PathBuf },
+    /// Write the built-in quizzes to a new quiz file, to edit and serve with `--data`
+    Seed { file: PathBuf },
+    /// Convert a CSV, XLSX, XLS or ODS spreadsheet to a quiz in the format of `data.json`
+    Import {
+        file: PathBuf,
+        /// Title of the quiz, the file name when missing
+        #[arg(long)]
+        title: Option<String>,
+    },
+    /// Print the JSON Schema of the quiz files like `data.json`
+    JsonSchema,
 }
 
-fn app() -> Router {
-    let in_memory_db = InMemoryDb::default();
-    let in_memory_broker = InMemoryBroker::default();
-    let schema = Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
-        .enable_federation()
-        .limit_complexity(100)
-        .data(in_memory_db)
-        .data(in_memory_broker)
-        .finish();
-
-    Router::new()
-        .route("/", get(graphiql).post(simple_graphql_handler))
-        .route_service("/ws", GraphQLSubscription::new(schema.clone()))
-        .layer(CorsLayer::permissive())
-        .layer(ServiceBuilder::new().layer(Extension(schema)))
+#[derive(Args)]
+struct ServeArgs {
+    #[command(flatten)]
+    server: ServerArgs,
+    /// Quiz file to serve instead of the built-in quizzes, it is checked like with `validate-data`
+    #[arg(long)]
+    data: Option<PathBuf>,
 }
 
 #[tokio::main]
 async fn main() {
-    let app = app();
-    let port = std::env::var("PORT")
-        .unwrap_or_else(|_| "4005".to_string())
-        .parse::<u16>()
-        .unwrap();
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Serve(args)) => serve(args).await,
+        Some(Command::PrintSchema) => print!("{}", quiz::schema_sdl()),
+        Some(Command::ValidateData { file }) => match quiz::validate_quiz_file(&file) {
+            Ok(quiz_count) => println!("{} is valid, quizzes: {quiz_count}", file.display()),
+            Err(problems) => {
+                for problem in problems {
+                    eprintln!("{problem}");
+                }
+                std::process::exit(1);
+            }
+        },
+        Some(Command::Seed { file }) => match quiz::seed_quiz_file(&file) {
+            Ok(()) => println!("{} written, serve it with --data", file.display()),
+            Err(err) => {
+                eprintln!("cannot write {}: {err}", file.display());
+                std::process::exit(1);
+            }
+        },
+        Some(Command::Import { file, title }) => match quiz::import_quiz_file(&file, title) {
+            Ok(quiz) => println!("{quiz}"),
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        },
+        Some(Command::JsonSchema) => println!("{}", quiz::quiz_file_json_schema()),
+        None => serve(cli.serve).await,
+    }
+}
 
-    println!(
-        "Explore this graph at https://studio.apollographql.com/sandbox/explorer?endpoint={}",
-        urlencoding::encode(&format!("http://localhost:{port}"))
-    );
+async fn serve(args: ServeArgs) {
+    let app = match quiz::app(args.data.as_deref()) {
+        Ok(app) => app,
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    };
 
-    Server::bind(&(Ipv4Addr::new(0, 0, 0, 0), port).into())
-        .serve(app.into_make_service())
-        .await
-        .unwrap();
+    subgraph_core::serve(app, args.server.addr(DEFAULT_PORT)).await;
 }
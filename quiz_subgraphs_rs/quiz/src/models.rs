@@ -1,178 +1,764 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
-use async_graphql::{Context, Object, SimpleObject, Subscription, ID};
+use async_graphql::{
+    ComplexObject, Context, Enum, Object, SimpleObject, Subscription, Union, Upload, ID,
+};
+use chrono::{DateTime, Utc};
 use futures_util::{Stream, StreamExt};
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use subgraph_core::Channels;
 use tokio::sync::{
     broadcast::{self},
-    RwLock,
+    RwLock, RwLockReadGuard,
 };
 use tokio_stream::wrappers::BroadcastStream;
+use uuid::Uuid;
+
+use crate::archive::{PastGame, ReplayEvent};
+use crate::autopilot::{AutoPilotSettings, AutoPilots, Step};
+use crate::event_log::{EventLog, GameEvent};
+use crate::export::{export_quiz, QuizExportFormat};
+use crate::history::{AnswerRecord, GameHistory, PlayerStats};
+use crate::import::import_quiz;
+use crate::locale::LocalizedText;
+use crate::question_bank::{player_choice_order, GameOptions, GameOptionsInput};
+use crate::quiz_file::{read_quiz_file, QuizFile};
+use crate::ranking::{RankedPlayer, Ranking};
+use crate::report::GameReport;
 
-pub(crate) struct InMemoryDb {
+pub struct InMemoryDb {
     quizzes: RwLock<HashMap<ID, Quiz>>,
-    // leaderboard by quiz id -> points by player_id
-    leaderboard: RwLock<HashMap<ID, HashMap<ID, usize>>>,
+    // games by session id, the current game of a quiz is the one of its `session_id`
+    games: RwLock<HashMap<ID, Game>>,
+    // finished games by game id, which is their session id
+    archive: RwLock<HashMap<ID, PastGame>>,
+    // every change made to the games, the maps above are only updated by applying its events
+    event_log: EventLog,
+}
+
+/// Everything happening during one game session of a quiz, kept once the game is over
+struct Game {
+    quiz_id: ID,
+    started_at: DateTime<Utc>,
+    // Players who joined the game or answered a question
+    participants: HashSet<ID>,
+    // Shared with the leaderboards made from it and copied when it changes, so their players,
+    // top players and ranks all come from the same ranking
+    ranking: Arc<Ranking>,
+    history: GameHistory,
+}
+
+impl Game {
+    /// Apply the part of an event about the game itself, returning the badges it unlocked
+    fn apply(&mut self, event: &GameEvent) -> Vec<Badge> {
+        match event {
+            GameEvent::QuestionOpened {
+                question_id, at, ..
+            } => {
+                // A question asked again starts over, its previous answers don't count
+                self.void_answers(question_id);
+                Arc::make_mut(&mut self.ranking).snapshot_ranks();
+                self.history.open_question(
+                    question_id,
+                    (*at - self.started_at).to_std().unwrap_or_default(),
+                );
+                Vec::new()
+            }
+            GameEvent::AnswerSubmitted {
+                player_id,
+                question_id,
+                correct,
+                answer_time,
+                ..
+            } => {
+                // Only the first answer of a player to a question counts
+                if self.history.has_answered(player_id, question_id) {
+                    return Vec::new();
+                }
+                self.participants.insert(player_id.clone());
+                Arc::make_mut(&mut self.ranking).add_points(
+                    player_id,
+                    *correct as usize,
+                    *answer_time,
+                );
+                self.history.record(
+                    player_id,
+                    AnswerRecord {
+                        question_id: question_id.clone(),
+                        correct: *correct,
+                        answer_time: *answer_time,
+                    },
+                )
+            }
+            GameEvent::QuestionSkipped { question_id, .. } => {
+                self.void_answers(question_id);
+                Vec::new()
+            }
+            GameEvent::PlayerJoined { player_id, .. } => {
+                self.participants.insert(player_id.clone());
+                Arc::make_mut(&mut self.ranking).join(player_id);
+                Vec::new()
+            }
+            GameEvent::QuizStarted { .. }
+            | GameEvent::QuestionClosed { .. }
+            | GameEvent::QuizFinished { .. }
+            | GameEvent::QuizPaused { .. }
+            | GameEvent::QuizResumed { .. }
+            | GameEvent::QuizAdded { .. } => Vec::new(),
+        }
+    }
+
+    fn void_answers(&mut self, question_id: &ID) {
+        for (player_id, answer) in self.history.void(question_id) {
+            Arc::make_mut(&mut self.ranking).remove_points(
+                &player_id,
+                answer.correct as usize,
+                answer.answer_time,
+            );
+        }
+    }
+
+    fn all_answered(&self, question_id: &ID) -> bool {
+        let (answered, expected) = self.progress(question_id);
+        expected > 0 && answered >= expected
+    }
+
+    // Number of players who answered the question and number of players expected to, without
+    // going through the answers as it's checked on every answer
+    fn progress(&self, question_id: &ID) -> (usize, usize) {
+        (
+            self.history.answered_count(question_id),
+            self.participants.len(),
+        )
+    }
 }
 
-const DATA_FILE: &str = include_str!("../data.json");
+pub(crate) const DATA_FILE: &str = include_str!("../data.json");
+
+// Join codes are numbers with this many digits, to type them easily on a phone
+const JOIN_CODE_DIGITS: u32 = 6;
+
+// A join code not used by any game in progress
+fn new_join_code(quizzes: &HashMap<ID, Quiz>) -> String {
+    loop {
+        let code = format!(
+            "{:0width$}",
+            Uuid::new_v4().as_u128() % 10u128.pow(JOIN_CODE_DIGITS),
+            width = JOIN_CODE_DIGITS as usize
+        );
+        if !quizzes
+            .values()
+            .any(|quiz| quiz.join_code.as_deref() == Some(&code))
+        {
+            return code;
+        }
+    }
+}
+
+/// Apply an event to the state of the quizzes and games, returning the badges it unlocked
+fn apply(
+    quizzes: &mut HashMap<ID, Quiz>,
+    games: &mut HashMap<ID, Game>,
+    archive: &mut HashMap<ID, PastGame>,
+    event: &GameEvent,
+) -> Vec<Badge> {
+    if let GameEvent::QuizAdded { quiz, .. } = event {
+        quizzes.insert(quiz.id.clone(), Quiz::clone(quiz).with_bank());
+        return Vec::new();
+    }
+    let Some(quiz) = quizzes.get_mut(event.quiz_id()) else {
+        return Vec::new();
+    };
+    let previous_session_id = quiz.session_id.clone();
+    quiz.apply(event);
+    match event {
+        GameEvent::QuizStarted {
+            quiz_id,
+            session_id,
+            at,
+            ..
+        } => {
+            // Only the current or last game of a quiz is kept, finished ones are in the archive
+            if let Some(previous_session_id) = previous_session_id {
+                games.remove(&previous_session_id);
+            }
+            let lobby = std::mem::take(&mut quiz.lobby);
+            let mut ranking = Ranking::default();
+            for player_id in &lobby {
+                ranking.join(player_id);
+            }
+            games.insert(
+                session_id.clone(),
+                Game {
+                    quiz_id: quiz_id.clone(),
+                    started_at: *at,
+                    participants: lobby.into_iter().collect(),
+                    ranking: Arc::new(ranking),
+                    history: Default::default(),
+                },
+            );
+        }
+        GameEvent::QuizFinished { session_id, at, .. } => {
+            if let Some(game) = games.get(session_id) {
+                archive.insert(
+                    session_id.clone(),
+                    PastGame::new(
+                        session_id,
+                        quiz,
+                        game.started_at,
+                        *at,
+                        &game.ranking,
+                        &game.history,
+                    ),
+                );
+            }
+        }
+        _ => {}
+    }
+
+    event
+        .session_id()
+        .and_then(|session_id| games.get_mut(session_id))
+        .map(|game| game.apply(event))
+        .unwrap_or_default()
+}
 
 impl InMemoryDb {
-    pub(crate) async fn next_question(&self, quiz_id: &ID) -> Option<Question> {
-        let mut quizzes = self.quizzes.write().await;
-        let quiz = quizzes.get_mut(quiz_id)?;
+    /// Serve the quizzes of a quiz file instead of the built-in ones, or the problems found in it
+    pub fn with_quiz_file(path: &Path) -> Result<Self, Vec<String>> {
+        let quizzes = read_quiz_file(path)?;
+        Ok(Self {
+            quizzes: RwLock::new(quizzes_by_id(quizzes)),
+            games: Default::default(),
+            archive: Default::default(),
+            event_log: Default::default(),
+        })
+    }
 
-        if quiz.current_question == -1 {
-            // Clean the leaderboard
-            let _ = self.leaderboard.write().await.remove(quiz_id);
+    /// Rebuild the games from the event log at `path`, new events are then appended to it
+    pub fn with_event_log(self, path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let (event_log, events) = EventLog::open(path.as_ref())?;
+        let mut quizzes = self.quizzes.into_inner();
+        let mut games = HashMap::new();
+        let mut archive = HashMap::new();
+        for event in &events {
+            apply(&mut quizzes, &mut games, &mut archive, event);
         }
-        quiz.current_question += 1;
 
-        if quiz.current_question == quiz.questions.len() as i8 {
-            quiz.current_question = -1;
-            // If there is no more question
-            None
-        } else {
-            let question = quiz.questions.get(quiz.current_question as usize).cloned();
+        Ok(Self {
+            quizzes: RwLock::new(quizzes),
+            games: RwLock::new(games),
+            archive: RwLock::new(archive),
+            event_log,
+        })
+    }
 
-            question
+    // Every event is written to the log before being applied, so the log is never behind
+    fn log(&self, event: &GameEvent) {
+        if let Err(err) = self.event_log.append(event) {
+            eprintln!("error when writing to the game log: {err}");
         }
     }
 
-    pub(crate) async fn get_player_points(&self, player_id: &ID, quiz_id: &ID) -> Option<usize> {
-        self.leaderboard
-            .read()
+    // Log and apply events built while holding the quizzes lock
+    async fn commit(&self, quizzes: &mut HashMap<ID, Quiz>, events: &[GameEvent]) {
+        let mut games = self.games.write().await;
+        let mut archive = self.archive.write().await;
+        for event in events {
+            self.log(event);
+            apply(quizzes, &mut games, &mut archive, event);
+        }
+    }
+
+    pub async fn next_question(&self, quiz_id: &ID) -> Option<Question> {
+        // Only an `index` can be out of range
+        self.open_question(quiz_id, None, None)
             .await
-            .get(quiz_id)?
-            .get(player_id)
-            .cloned()
+            .unwrap_or_default()
     }
 
-    pub(crate) async fn compute_leaderboard(&self, quiz_id: &ID) -> Option<Leaderboard> {
-        let quiz = self.quizzes.read().await.get(quiz_id)?.clone();
-        self.leaderboard.read().await.get(quiz_id).map(|players| {
-            let mut list: Vec<Player> = players
-                .iter()
-                .map(|(player_id, points)| Player {
-                    id: player_id.clone(),
-                    points: *points,
+    /// Open the question at `index` or the next one, the game is over after the last question.
+    /// When the game is started, its questions are drawn with `options`. An error if there is no
+    /// question at `index` in the game
+    pub(crate) async fn open_question(
+        &self,
+        quiz_id: &ID,
+        index: Option<usize>,
+        options: Option<GameOptionsInput>,
+    ) -> async_graphql::Result<Option<Question>> {
+        let mut quizzes = self.quizzes.write().await;
+        let Some(quiz) = quizzes.get(quiz_id) else {
+            return Ok(None);
+        };
+        let at = Utc::now();
+
+        let mut events = Vec::new();
+        let (session_id, questions) = match quiz.current_question {
+            None => {
+                let session_id = ID::from(Uuid::new_v4().to_string());
+                let options = GameOptions::from(options.unwrap_or_default());
+                let questions = options.draw(&quiz.bank);
+                events.push(GameEvent::QuizStarted {
                     quiz_id: quiz_id.clone(),
-                })
-                .collect();
+                    session_id: session_id.clone(),
+                    join_code: Some(new_join_code(&quizzes)),
+                    options,
+                    at,
+                });
+                (session_id, questions)
+            }
+            Some(current_question) => {
+                let Some(session_id) = quiz.session_id.clone() else {
+                    return Ok(None);
+                };
+                if let Some(question) = quiz
+                    .questions
+                    .get(current_question)
+                    .filter(|_| quiz.question_opened_at.is_some())
+                {
+                    events.push(GameEvent::QuestionClosed {
+                        quiz_id: quiz_id.clone(),
+                        session_id: session_id.clone(),
+                        question_id: question.id.clone(),
+                        at,
+                    });
+                }
+                (session_id, quiz.questions.clone())
+            }
+        };
+        // Checked against the questions of this game, which are only drawn when it starts
+        let question = match index {
+            Some(index) => Some(
+                questions
+                    .get(index)
+                    .cloned()
+                    .ok_or_else(|| async_graphql::Error::new("question not found"))?,
+            ),
+            None => questions
+                .get(quiz.current_question.map_or(0, |current| current + 1))
+                .cloned(),
+        };
+        events.push(match &question {
+            Some(question) => GameEvent::QuestionOpened {
+                quiz_id: quiz_id.clone(),
+                session_id,
+                question_id: question.id.clone(),
+                at,
+            },
+            // If there is no more question
+            None => GameEvent::QuizFinished {
+                quiz_id: quiz_id.clone(),
+                session_id,
+                at,
+            },
+        });
+        self.commit(&mut quizzes, &events).await;
+
+        Ok(question)
+    }
+
+    /// Stop accepting answers to the current question, return the closed question id
+    pub(crate) async fn close_question(&self, quiz_id: &ID) -> Option<ID> {
+        let mut quizzes = self.quizzes.write().await;
+        let (session_id, question_id) = quizzes.get(quiz_id).and_then(Quiz::opened_question)?;
+        let event = GameEvent::QuestionClosed {
+            quiz_id: quiz_id.clone(),
+            session_id,
+            question_id: question_id.clone(),
+            at: Utc::now(),
+        };
+        self.commit(&mut quizzes, &[event]).await;
+        Some(question_id)
+    }
+
+    /// Close the current question without counting its answers, return the skipped question id
+    pub(crate) async fn skip_question(&self, quiz_id: &ID) -> Option<ID> {
+        let mut quizzes = self.quizzes.write().await;
+        let (session_id, question_id) = quizzes.get(quiz_id).and_then(Quiz::opened_question)?;
+        let event = GameEvent::QuestionSkipped {
+            quiz_id: quiz_id.clone(),
+            session_id,
+            question_id: question_id.clone(),
+            at: Utc::now(),
+        };
+        self.commit(&mut quizzes, &[event]).await;
+        Some(question_id)
+    }
+
+    /// Reject answers and stop the answer time, return false if the game is not in progress or
+    /// already paused
+    pub(crate) async fn pause(&self, quiz_id: &ID) -> bool {
+        let mut quizzes = self.quizzes.write().await;
+        let Some(session_id) = quizzes
+            .get(quiz_id)
+            .filter(|quiz| quiz.current_question.is_some() && quiz.paused_at.is_none())
+            .and_then(|quiz| quiz.session_id.clone())
+        else {
+            return false;
+        };
+        let event = GameEvent::QuizPaused {
+            quiz_id: quiz_id.clone(),
+            session_id,
+            at: Utc::now(),
+        };
+        self.commit(&mut quizzes, &[event]).await;
+        true
+    }
 
-            list.sort_by(|a, b| b.points.cmp(&a.points));
+    /// Return false if the game wasn't paused
+    pub(crate) async fn resume(&self, quiz_id: &ID) -> bool {
+        let mut quizzes = self.quizzes.write().await;
+        let Some(session_id) = quizzes
+            .get(quiz_id)
+            .filter(|quiz| quiz.paused_at.is_some())
+            .and_then(|quiz| quiz.session_id.clone())
+        else {
+            return false;
+        };
+        let event = GameEvent::QuizResumed {
+            quiz_id: quiz_id.clone(),
+            session_id,
+            at: Utc::now(),
+        };
+        self.commit(&mut quizzes, &[event]).await;
+        true
+    }
+
+    /// Register a player in the game of a quiz, or in the next one if it isn't started.
+    /// Return false if the quiz doesn't exist
+    pub(crate) async fn join(&self, player_id: &ID, quiz_id: &ID) -> bool {
+        let mut quizzes = self.quizzes.write().await;
+        let Some(quiz) = quizzes.get(quiz_id) else {
+            return false;
+        };
+        let session_id = quiz
+            .session_id
+            .clone()
+            .filter(|_| quiz.current_question.is_some());
+        let joined = match &session_id {
+            Some(session_id) => self
+                .games
+                .read()
+                .await
+                .get(session_id)
+                .is_some_and(|game| game.participants.contains(player_id)),
+            None => quiz.lobby.contains(player_id),
+        };
+        if !joined {
+            let event = GameEvent::PlayerJoined {
+                quiz_id: quiz_id.clone(),
+                session_id,
+                player_id: player_id.clone(),
+                at: Utc::now(),
+            };
+            self.commit(&mut quizzes, &[event]).await;
+        }
+        true
+    }
 
-            Leaderboard { quiz, list }
+    /// Number of players who answered the current question of a quiz and number of players
+    /// expected to, `None` if no question is opened
+    pub(crate) async fn question_progress(&self, quiz_id: &ID) -> Option<(usize, usize)> {
+        let quizzes = self.quizzes.read().await;
+        let quiz = quizzes.get(quiz_id)?;
+        let question = quiz.questions.get(quiz.current_question?)?;
+        let games = self.games.read().await;
+        Some(games.get(quiz.session_id.as_ref()?)?.progress(&question.id))
+    }
+
+    /// Whether every player of the game answered the current question
+    pub(crate) async fn all_answered(&self, quiz_id: &ID) -> bool {
+        let quizzes = self.quizzes.read().await;
+        let Some(quiz) = quizzes.get(quiz_id) else {
+            return false;
+        };
+        let (Some(question), Some(session_id)) = (
+            quiz.current_question
+                .and_then(|index| quiz.questions.get(index)),
+            &quiz.session_id,
+        ) else {
+            return false;
+        };
+        let games = self.games.read().await;
+        games
+            .get(session_id)
+            .is_some_and(|game| game.all_answered(&question.id))
+    }
+
+    async fn current_game(&self, quiz_id: &ID) -> Option<RwLockReadGuard<'_, Game>> {
+        let session_id = self.quizzes.read().await.get(quiz_id)?.session_id.clone()?;
+        RwLockReadGuard::try_map(self.games.read().await, |games| games.get(&session_id)).ok()
+    }
+
+    pub(crate) async fn get_player(&self, player_id: &ID, quiz_id: &ID) -> Option<Player> {
+        self.current_game(quiz_id)
+            .await?
+            .ranking
+            .get(player_id)
+            .map(|player| Player::ranked(quiz_id, player))
+    }
+
+    pub(crate) async fn compute_leaderboard(&self, quiz_id: &ID) -> Option<Leaderboard> {
+        let game = self.current_game(quiz_id).await?;
+        let list: Vec<Player> = game
+            .ranking
+            .iter()
+            .map(|player| Player::ranked(quiz_id, player))
+            .collect();
+
+        Some(Leaderboard {
+            quiz_id: quiz_id.clone(),
+            page_info: PageInfo {
+                end_cursor: list.last().map(|player| player.id.to_string()),
+                has_next_page: false,
+            },
+            list,
+            ranking: game.ranking.clone(),
         })
     }
 
-    pub(crate) async fn answer(
+    /// Leaderboard with only `first` players, starting after the player whose ID is the `after` cursor
+    pub(crate) async fn leaderboard_page(
+        &self,
+        quiz_id: &ID,
+        first: usize,
+        after: Option<&ID>,
+    ) -> async_graphql::Result<Option<Leaderboard>> {
+        let Some(game) = self.current_game(quiz_id).await else {
+            return Ok(None);
+        };
+        let (page, has_next_page) = game
+            .ranking
+            .page(after, first)
+            .ok_or_else(|| async_graphql::Error::new("invalid cursor"))?;
+        let list: Vec<Player> = page
+            .into_iter()
+            .map(|player| Player::ranked(quiz_id, player))
+            .collect();
+
+        Ok(Some(Leaderboard {
+            quiz_id: quiz_id.clone(),
+            page_info: PageInfo {
+                end_cursor: list.last().map(|player| player.id.to_string()),
+                has_next_page,
+            },
+            list,
+            ranking: game.ranking.clone(),
+        }))
+    }
+
+    pub async fn answer(
         &self,
         player_id: &ID,
         quiz_id: &ID,
-        _question_id: &ID,
+        question_id: &ID,
         choice_id: Option<&ID>,
-    ) -> Option<(Response, Leaderboard)> {
-        let quiz = dbg!(self.get_quiz(quiz_id).await)?;
-        if quiz.current_question < 0 {
+    ) -> Option<AnswerOutcome> {
+        // Held until the answer is applied, so the question can't be closed in between
+        let quizzes = self.quizzes.read().await;
+        let quiz = quizzes.get(quiz_id)?;
+        let current_question = quiz.questions.get(quiz.current_question?)?;
+        // A late answer to a previous question
+        if &current_question.id != question_id {
             return None;
         }
-        let current_question = dbg!(quiz.questions.get(quiz.current_question as usize))?;
-        let right_choice = dbg!(current_question
+        // The question is closed or the game paused
+        let opened_at = quiz
+            .question_opened_at
+            .filter(|_| quiz.paused_at.is_none())?;
+        let right_choice = current_question
             .choices
             .iter()
-            .find(|c| c.id == current_question.good_answer))?;
-        let incr = match choice_id {
-            Some(choice_id) => {
-                if &right_choice.id == choice_id {
-                    1
-                } else {
-                    0
-                }
-            }
-            None => 0,
+            .find(|c| c.id == current_question.good_answer)?
+            .clone();
+        let answer_time = (Utc::now() - opened_at).to_std().unwrap_or_default();
+        let event = GameEvent::AnswerSubmitted {
+            quiz_id: quiz_id.clone(),
+            session_id: quiz.session_id.clone()?,
+            player_id: player_id.clone(),
+            question_id: question_id.clone(),
+            choice_id: choice_id.cloned(),
+            correct: choice_id == Some(&right_choice.id),
+            answer_time,
         };
-        self.leaderboard
-            .write()
-            .await
-            .entry(quiz_id.clone())
-            .and_modify(|quiz| {
-                quiz.entry(player_id.clone())
-                    .and_modify(|score| {
-                        *score += incr;
-                    })
-                    .or_insert(incr);
-            })
-            .or_insert_with(|| [(player_id.clone(), incr)].into());
 
-        let leaderboard = dbg!(self.compute_leaderboard(quiz_id).await);
+        let mut games = self.games.write().await;
+        let game = games.get_mut(event.session_id()?)?;
+        if game.history.has_answered(player_id, question_id) {
+            return None;
+        }
+        let all_answered_before = game.all_answered(question_id);
+        // Answers only change the game, see `apply`
+        self.log(&event);
+        let badges = game.apply(&event);
+        let (answered_count, _) = game.progress(question_id);
 
-        leaderboard.map(|l| {
-            (
-                Response {
-                    success: incr == 1,
-                    right_choice: right_choice.clone(),
-                },
-                l,
-            )
+        Some(AnswerOutcome {
+            response: Response {
+                success: matches!(event, GameEvent::AnswerSubmitted { correct: true, .. }),
+                right_choice,
+            },
+            badges,
+            all_answered: (!all_answered_before && game.all_answered(question_id)).then(|| {
+                AllAnswered {
+                    question_id: question_id.clone(),
+                    answered_count,
+                }
+            }),
         })
     }
 
+    pub(crate) async fn player_stats(&self, player_id: &ID, quiz_id: &ID) -> PlayerStats {
+        self.current_game(quiz_id)
+            .await
+            .and_then(|game| game.history.player(player_id).map(|player| player.stats()))
+            .unwrap_or_default()
+    }
+
+    pub(crate) async fn game_report(&self, session_id: &ID) -> Option<GameReport> {
+        // Locked in the same order as in `commit`
+        let quizzes = self.quizzes.read().await;
+        let games = self.games.read().await;
+        match games.get(session_id) {
+            Some(game) => Some(GameReport::new(
+                session_id,
+                quizzes.get(&game.quiz_id)?,
+                &game.ranking,
+                &game.history,
+            )),
+            None => self
+                .archive
+                .read()
+                .await
+                .get(session_id)
+                .map(|past_game| past_game.report.clone()),
+        }
+    }
+
+    /// Finished games of a quiz, the most recent first
+    pub(crate) async fn past_games(&self, quiz_id: &ID) -> Vec<PastGame> {
+        let mut past_games: Vec<PastGame> = self
+            .archive
+            .read()
+            .await
+            .values()
+            .filter(|game| &game.quiz_id == quiz_id)
+            .cloned()
+            .collect();
+        past_games.sort_by_key(|game| std::cmp::Reverse(game.finished_at));
+        past_games
+    }
+
+    pub(crate) async fn past_game(&self, game_id: &ID) -> Option<PastGame> {
+        self.archive.read().await.get(game_id).cloned()
+    }
+
+    /// Add a quiz to the ones of the quiz file, return false if there is one with the same id
+    pub(crate) async fn add_quiz(&self, quiz: Quiz) -> bool {
+        let mut quizzes = self.quizzes.write().await;
+        if quizzes.contains_key(&quiz.id) {
+            return false;
+        }
+        let event = GameEvent::QuizAdded {
+            quiz: Box::new(quiz),
+            at: Utc::now(),
+        };
+        self.commit(&mut quizzes, &[event]).await;
+        true
+    }
+
     pub(crate) async fn get_quiz(&self, quiz_id: &ID) -> Option<Quiz> {
         self.quizzes.read().await.get(quiz_id).cloned()
     }
 
+    /// Quiz with a game in progress for this join code
+    pub(crate) async fn get_quiz_by_code(&self, code: &str) -> Option<Quiz> {
+        self.quizzes
+            .read()
+            .await
+            .values()
+            .find(|quiz| quiz.join_code.as_deref() == Some(code))
+            .cloned()
+    }
+
     pub(crate) async fn get_quizzes(&self) -> Vec<Quiz> {
         self.quizzes.read().await.values().cloned().collect()
     }
 }
 
-#[derive(Default)]
-pub(crate) struct InMemoryBroker {
-    quizzes: RwLock<HashMap<ID, broadcast::Sender<Question>>>,
-    leaderboard: RwLock<HashMap<ID, broadcast::Sender<Leaderboard>>>,
+// Host controls can follow each other quickly, unlike questions
+const QUIZ_CHANNEL_CAPACITY: usize = 16;
+
+pub const DEFAULT_LEADERBOARD_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct InMemoryBroker {
+    quizzes: Channels<QuizEvent>,
+    leaderboard: RwLock<HashMap<ID, LeaderboardChannel>>,
+    achievements: Channels<Achievement>,
+    // Minimum time between two leaderboard broadcasts for a quiz
+    leaderboard_interval: Duration,
 }
 
-impl InMemoryBroker {
-    pub(crate) async fn subscribe_quiz(&self, quiz_id: &ID) -> impl Stream<Item = Question> {
-        let quiz_stream = self.quizzes.read().await.get(quiz_id).map(|s| {
-            BroadcastStream::new(s.subscribe())
-                .filter_map(|e| async move { e.ok() })
-                .boxed()
-        });
+struct LeaderboardChannel {
+    sender: broadcast::Sender<Leaderboard>,
+    // Set while a broadcast is scheduled, answers coming in meanwhile are part of it
+    flush_pending: Arc<AtomicBool>,
+}
 
-        match quiz_stream {
-            Some(quiz_stream) => quiz_stream,
-            None => {
-                let (tx, rx) = broadcast::channel(2);
-                self.quizzes.write().await.insert(quiz_id.clone(), tx);
-                BroadcastStream::new(rx)
-                    .filter_map(|e| async move { e.ok() })
-                    .boxed()
-            }
+impl InMemoryBroker {
+    pub fn new(leaderboard_interval: Duration) -> Self {
+        Self {
+            quizzes: Channels::new(QUIZ_CHANNEL_CAPACITY),
+            leaderboard: Default::default(),
+            achievements: Channels::default(),
+            leaderboard_interval,
         }
     }
 
-    pub(crate) async fn next_question(&self, quiz_id: &ID, question: Question) {
-        if let Some(quiz_broker) = self.quizzes.read().await.get(quiz_id) {
-            let _err = quiz_broker.send(question);
-        }
+    pub(crate) fn leaderboard_interval(&self) -> Duration {
+        self.leaderboard_interval
+    }
+
+    pub(crate) async fn subscribe_quiz(&self, quiz_id: &ID) -> impl Stream<Item = QuizEvent> {
+        self.quizzes.subscribe(quiz_id).await
+    }
+
+    pub(crate) async fn quiz_event(&self, quiz_id: &ID, event: QuizEvent) {
+        self.quizzes.send(quiz_id, event).await;
     }
 
     pub(crate) async fn unsubscribe_quiz(&self, quiz_id: &ID) {
-        self.quizzes.write().await.remove(quiz_id);
+        self.quizzes.close(quiz_id).await;
     }
 
     pub(crate) async fn unsubscribe_leaderboard(&self, quiz_id: &ID) {
         self.leaderboard.write().await.remove(quiz_id);
     }
 
-    pub(crate) async fn subscribe_leaderboard(
+    pub(crate) async fn subscribe_achievements(
         &self,
         quiz_id: &ID,
-    ) -> impl Stream<Item = Leaderboard> {
+    ) -> impl Stream<Item = Achievement> {
+        self.achievements.subscribe(quiz_id).await
+    }
+
+    pub(crate) async fn achievement_unlocked(&self, quiz_id: &ID, achievement: Achievement) {
+        self.achievements.send(quiz_id, achievement).await;
+    }
+
+    pub(crate) async fn unsubscribe_achievements(&self, quiz_id: &ID) {
+        self.achievements.close(quiz_id).await;
+    }
+
+    pub async fn subscribe_leaderboard(&self, quiz_id: &ID) -> impl Stream<Item = Leaderboard> {
         let leaderboard_stream = self.leaderboard.read().await.get(quiz_id).map(|s| {
-            BroadcastStream::new(s.subscribe())
+            BroadcastStream::new(s.sender.subscribe())
                 .filter_map(|e| async move { e.ok() })
                 .boxed()
         });
@@ -180,8 +766,14 @@ impl InMemoryBroker {
         match leaderboard_stream {
             Some(leaderboard_stream) => leaderboard_stream,
             None => {
-                let (tx, rx) = broadcast::channel(2);
-                self.leaderboard.write().await.insert(quiz_id.clone(), tx);
+                let (sender, rx) = broadcast::channel(2);
+                self.leaderboard.write().await.insert(
+                    quiz_id.clone(),
+                    LeaderboardChannel {
+                        sender,
+                        flush_pending: Default::default(),
+                    },
+                );
                 BroadcastStream::new(rx)
                     .filter_map(|e| async move { e.ok() })
                     .boxed()
@@ -189,30 +781,107 @@ impl InMemoryBroker {
         }
     }
 
-    pub(crate) async fn broadcast_leaderboard(&self, quiz_id: &ID, leaderboard: Leaderboard) {
-        if let Some(leaderboard_broker) = self.leaderboard.read().await.get(quiz_id) {
-            let err = leaderboard_broker.send(leaderboard);
-            if let Err(err) = err {
-                eprintln!("error when broadcasting leaderboard: {err}");
-            }
+    /// Broadcast the leaderboard of a quiz once the leaderboard interval is elapsed.
+    ///
+    /// Every answer coming in before that is coalesced in the same broadcast, so the leaderboard
+    /// is computed at most once per interval whatever the number of players.
+    pub async fn schedule_leaderboard(&self, in_memory_db: &Arc<InMemoryDb>, quiz_id: &ID) {
+        let (sender, flush_pending) = match self.leaderboard.read().await.get(quiz_id) {
+            Some(channel) => (channel.sender.clone(), channel.flush_pending.clone()),
+            None => return,
+        };
+        if flush_pending.swap(true, Ordering::AcqRel) {
+            return;
         }
+
+        let in_memory_db = in_memory_db.clone();
+        let quiz_id = quiz_id.clone();
+        let leaderboard_interval = self.leaderboard_interval;
+        tokio::spawn(async move {
+            tokio::time::sleep(leaderboard_interval).await;
+            // Answers from now on need another broadcast
+            flush_pending.store(false, Ordering::Release);
+            if let Some(leaderboard) = in_memory_db.compute_leaderboard(&quiz_id).await {
+                if let Err(err) = sender.send(leaderboard) {
+                    eprintln!("error when broadcasting leaderboard: {err}");
+                }
+            }
+        });
+    }
+}
+
+impl Default for InMemoryBroker {
+    fn default() -> Self {
+        Self::new(DEFAULT_LEADERBOARD_INTERVAL)
     }
 }
 
 pub(crate) struct QueryRoot;
 
-#[Object]
+#[Object(name = "Query")]
 impl QueryRoot {
     async fn all_quizzes<'ctx>(&self, ctx: &Context<'ctx>) -> Vec<Quiz> {
-        let in_memory_db: &InMemoryDb = ctx.data_unchecked();
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
 
         in_memory_db.get_quizzes().await
     }
 
-    async fn leaderboard_for_quiz<'ctx>(&self, ctx: &Context<'ctx>, id: ID) -> Option<Leaderboard> {
-        let in_memory_db: &InMemoryDb = ctx.data_unchecked();
+    /// Get the report of a game session, available once the game started and kept when it's over
+    async fn game_report<'ctx>(&self, ctx: &Context<'ctx>, session_id: ID) -> Option<GameReport> {
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
 
-        in_memory_db.compute_leaderboard(&id).await
+        in_memory_db.game_report(&session_id).await
+    }
+
+    /// Quiz of the game in progress with this join code, null once the game is over
+    async fn game_by_code<'ctx>(&self, ctx: &Context<'ctx>, code: String) -> Option<Quiz> {
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
+
+        in_memory_db.get_quiz_by_code(&code).await
+    }
+
+    /// Questions of a quiz as JSON, in the format of `data.json`, or as a Markdown answer key
+    async fn export_quiz<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        id: ID,
+        format: QuizExportFormat,
+    ) -> async_graphql::Result<String> {
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
+
+        let quiz = in_memory_db
+            .get_quiz(&id)
+            .await
+            .ok_or_else(|| async_graphql::Error::new("quiz not found"))?;
+        Ok(export_quiz(&quiz, format)?)
+    }
+
+    /// Finished games of a quiz, the most recent first
+    async fn past_games<'ctx>(&self, ctx: &Context<'ctx>, quiz_id: ID) -> Vec<PastGame> {
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
+
+        in_memory_db.past_games(&quiz_id).await
+    }
+
+    /// Without `first` the whole leaderboard is returned, `after` is the `endCursor` of the previous page
+    async fn leaderboard_for_quiz<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        id: ID,
+        first: Option<usize>,
+        after: Option<ID>,
+    ) -> async_graphql::Result<Option<Leaderboard>> {
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
+
+        match first {
+            Some(first) => {
+                in_memory_db
+                    .leaderboard_page(&id, first, after.as_ref())
+                    .await
+            }
+            None if after.is_some() => Err("`after` cannot be used without `first`".into()),
+            None => Ok(in_memory_db.compute_leaderboard(&id).await),
+        }
     }
 
     #[graphql(entity)]
@@ -222,35 +891,55 @@ impl QueryRoot {
         id: ID,
         quiz_id: ID,
     ) -> Player {
-        let in_memory_db: &InMemoryDb = ctx.data_unchecked();
-        let points = in_memory_db
-            .get_player_points(&id, &quiz_id)
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
+        in_memory_db
+            .get_player(&id, &quiz_id)
             .await
-            .unwrap_or_default();
-        Player {
-            id,
-            quiz_id,
-            points,
-        }
+            .unwrap_or(Player {
+                id,
+                quiz_id,
+                points: 0,
+                rank: None,
+                rank_change: None,
+            })
     }
 
     #[graphql(entity)]
     async fn find_quiz_by_id<'ctx>(&self, ctx: &Context<'ctx>, id: ID) -> Option<Quiz> {
-        let in_memory_db: &InMemoryDb = ctx.data_unchecked();
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
         in_memory_db.get_quiz(&id).await
     }
 }
 
 pub(crate) struct SubscriptionRoot;
 
-#[Subscription]
+#[Subscription(name = "Subscription")]
 impl SubscriptionRoot {
     async fn new_question<'ctx>(
         &self,
         ctx: &Context<'ctx>,
         quiz_id: ID,
     ) -> impl Stream<Item = Question> {
-        let in_memory_broker: &InMemoryBroker = ctx.data_unchecked();
+        let in_memory_broker: &Arc<InMemoryBroker> = ctx.data_unchecked();
+
+        in_memory_broker
+            .subscribe_quiz(&quiz_id)
+            .await
+            .filter_map(|event| async move {
+                match event {
+                    QuizEvent::Question(question) => Some(question),
+                    _ => None,
+                }
+            })
+    }
+
+    /// Listens for the questions of a quiz and what the host does with them
+    async fn quiz_events<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        quiz_id: ID,
+    ) -> impl Stream<Item = QuizEvent> {
+        let in_memory_broker: &Arc<InMemoryBroker> = ctx.data_unchecked();
 
         in_memory_broker.subscribe_quiz(&quiz_id).await
     }
@@ -260,15 +949,47 @@ impl SubscriptionRoot {
         ctx: &Context<'ctx>,
         id: ID,
     ) -> impl Stream<Item = Leaderboard> {
-        let in_memory_broker: &InMemoryBroker = ctx.data_unchecked();
+        let in_memory_broker: &Arc<InMemoryBroker> = ctx.data_unchecked();
 
         in_memory_broker.subscribe_leaderboard(&id).await
     }
+
+    /// Listens the badges unlocked by players during a quiz
+    async fn achievements_for_quiz<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        quiz_id: ID,
+    ) -> impl Stream<Item = Achievement> {
+        let in_memory_broker: &Arc<InMemoryBroker> = ctx.data_unchecked();
+
+        in_memory_broker.subscribe_achievements(&quiz_id).await
+    }
+
+    /// Sends the questions and leaderboards of a finished game again, `speed` times faster
+    /// than the original game
+    async fn replay_game<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        game_id: ID,
+        #[graphql(default = 1.0)] speed: f64,
+    ) -> async_graphql::Result<impl Stream<Item = ReplayEvent>> {
+        if !(speed > 0.0 && speed.is_finite()) {
+            return Err(async_graphql::Error::new("speed must be positive"));
+        }
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
+        let in_memory_broker: &Arc<InMemoryBroker> = ctx.data_unchecked();
+
+        let game = in_memory_db
+            .past_game(&game_id)
+            .await
+            .ok_or_else(|| async_graphql::Error::new("game not found"))?;
+        Ok(game.replay(speed, in_memory_broker.leaderboard_interval()))
+    }
 }
 
 pub(crate) struct MutationRoot;
 
-#[Object]
+#[Object(name = "Mutation")]
 impl MutationRoot {
     async fn answer<'ctx>(
         &self,
@@ -277,115 +998,683 @@ impl MutationRoot {
         question_id: ID,
         choice_id: Option<ID>,
     ) -> async_graphql::Result<Response> {
-        let player_id: &ID = match ctx.data_opt() {
-            Some(id) => id,
-            None => {
-                return Err("cannot find the player header".into());
-            }
-        };
+        let player_id = subgraph_core::player_id(ctx)?;
 
-        let in_memory_db: &InMemoryDb = ctx.data_unchecked();
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
 
-        let (response, leaderboard) = in_memory_db
+        let AnswerOutcome {
+            response,
+            badges,
+            all_answered,
+        } = in_memory_db
             .answer(player_id, &quiz_id, &question_id, choice_id.as_ref())
             .await
             .ok_or_else(|| async_graphql::Error::new("cannot answer"))?;
 
-        let in_memory_broker: &InMemoryBroker = ctx.data_unchecked();
+        let auto_pilots: &AutoPilots = ctx.data_unchecked();
+        auto_pilots.answered(&quiz_id).await;
+
+        let in_memory_broker: &Arc<InMemoryBroker> = ctx.data_unchecked();
+        if let Some(all_answered) = all_answered {
+            in_memory_broker
+                .quiz_event(&quiz_id, QuizEvent::AllAnswered(all_answered))
+                .await;
+        }
         in_memory_broker
-            .broadcast_leaderboard(&quiz_id, leaderboard)
+            .schedule_leaderboard(in_memory_db, &quiz_id)
             .await;
+        if !badges.is_empty() {
+            if let Some(player) = in_memory_db.get_player(player_id, &quiz_id).await {
+                for badge in badges {
+                    in_memory_broker
+                        .achievement_unlocked(
+                            &quiz_id,
+                            Achievement {
+                                player: player.clone(),
+                                badge,
+                                question_id: question_id.clone(),
+                            },
+                        )
+                        .await;
+                }
+            }
+        }
 
         Ok(response)
     }
 
-    async fn next_question<'ctx>(&self, ctx: &Context<'ctx>, quiz_id: ID) -> Option<Question> {
-        let in_memory_db: &InMemoryDb = ctx.data_unchecked();
-        let in_memory_broker: &InMemoryBroker = ctx.data_unchecked();
+    /// Add a quiz from a CSV, XLSX, XLS or ODS spreadsheet with one question by row and the
+    /// columns of the Kahoot template: "Question", "Answer 1" to "Answer 4", "Time limit (sec)"
+    /// and "Correct answer" (from 1 to 4). Its title is the file name without `title`
+    async fn import_quiz<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        file: Upload,
+        title: Option<String>,
+    ) -> async_graphql::Result<Quiz> {
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
+
+        let upload = file.value(ctx)?;
+        let file_name = upload.filename.clone();
+        let mut content = Vec::new();
+        upload.into_read().read_to_end(&mut content)?;
+        let quiz = import_quiz(&file_name, &content, title).map_err(async_graphql::Error::new)?;
+        if !in_memory_db.add_quiz(quiz.clone()).await {
+            return Err(async_graphql::Error::new(
+                "a quiz with this id already exists",
+            ));
+        }
+
+        Ok(quiz)
+    }
+
+    /// Join the game of a quiz with the player from the `player` header, or the next one if it
+    /// isn't started. Players who answer a question join the game too
+    async fn join_game<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        quiz_id: ID,
+    ) -> async_graphql::Result<bool> {
+        let player_id = subgraph_core::player_id(ctx)?;
+
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
+        if !in_memory_db.join(player_id, &quiz_id).await {
+            return Err(async_graphql::Error::new("quiz not found"));
+        }
+
+        Ok(true)
+    }
+
+    /// For admin to trigger the next question. The first one starts the game, with `options`
+    /// to draw its questions
+    async fn next_question<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        quiz_id: ID,
+        options: Option<GameOptionsInput>,
+    ) -> async_graphql::Result<Option<Question>> {
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
+        let in_memory_broker: &Arc<InMemoryBroker> = ctx.data_unchecked();
+        let auto_pilots: &AutoPilots = ctx.data_unchecked();
 
-        let _ = in_memory_broker.subscribe_quiz(&quiz_id).await;
-        let question = in_memory_db.next_question(&quiz_id).await;
-        match question {
-            Some(question) => {
-                in_memory_broker
-                    .next_question(&quiz_id, question.clone())
-                    .await;
-                Some(question)
+        if let Some(options) = &options {
+            if options.question_count == Some(0) {
+                return Err(async_graphql::Error::new("questionCount must be positive"));
             }
-            None => {
-                in_memory_broker.unsubscribe_quiz(&quiz_id).await;
-                in_memory_broker.unsubscribe_leaderboard(&quiz_id).await;
-                None
+            let quiz = in_memory_db
+                .get_quiz(&quiz_id)
+                .await
+                .ok_or_else(|| async_graphql::Error::new("quiz not found"))?;
+            if quiz.current_question.is_some() {
+                return Err(async_graphql::Error::new(
+                    "the game is already started, options are only used to start it",
+                ));
             }
         }
+
+        auto_pilots
+            .open_question(in_memory_db, in_memory_broker, &quiz_id, None, options)
+            .await
+    }
+
+    /// Close the current question and open the one at `index` (starting at 0) instead, to
+    /// recover from a mistake. The game is started if needed
+    async fn go_to_question<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        quiz_id: ID,
+        index: usize,
+    ) -> async_graphql::Result<Question> {
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
+        let in_memory_broker: &Arc<InMemoryBroker> = ctx.data_unchecked();
+        let auto_pilots: &AutoPilots = ctx.data_unchecked();
+
+        auto_pilots
+            .open_question(in_memory_db, in_memory_broker, &quiz_id, Some(index), None)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("quiz not found"))
+    }
+
+    /// Close the current question without counting its answers, return false if no question
+    /// was opened
+    async fn skip_question<'ctx>(&self, ctx: &Context<'ctx>, quiz_id: ID) -> bool {
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
+        let in_memory_broker: &Arc<InMemoryBroker> = ctx.data_unchecked();
+        let auto_pilots: &AutoPilots = ctx.data_unchecked();
+
+        auto_pilots
+            .skip_question(in_memory_db, in_memory_broker, &quiz_id)
+            .await
+    }
+
+    /// Let the server open each question, close it after its time limit (`questionTime` seconds
+    /// unless the question has its own) or once every player answered, and move on after
+    /// showing the results for `resultsTime` seconds. The game is started if needed
+    async fn start_auto_pilot<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        quiz_id: ID,
+        #[graphql(default = 20)] question_time: u32,
+        #[graphql(default = 5)] results_time: u32,
+    ) -> async_graphql::Result<bool> {
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
+        let in_memory_broker: &Arc<InMemoryBroker> = ctx.data_unchecked();
+        let auto_pilots: &AutoPilots = ctx.data_unchecked();
+
+        let quiz = in_memory_db
+            .get_quiz(&quiz_id)
+            .await
+            .ok_or_else(|| async_graphql::Error::new("quiz not found"))?;
+        let settings = AutoPilotSettings {
+            question_time: Duration::from_secs(question_time.into()),
+            results_time: Duration::from_secs(results_time.into()),
+        };
+        let step = Step::starting(
+            &settings,
+            quiz.current_question
+                .and_then(|current| quiz.questions.get(current)),
+            quiz.question_opened_at.is_some(),
+        );
+        if !auto_pilots
+            .start(
+                in_memory_db,
+                in_memory_broker,
+                &quiz_id,
+                settings,
+                step,
+                quiz.paused_at.is_some(),
+            )
+            .await
+        {
+            return Err(async_graphql::Error::new(
+                "the auto-pilot is already on for this quiz",
+            ));
+        }
+
+        Ok(true)
+    }
+
+    /// Reject answers and freeze the countdown of the auto-pilot until the quiz is resumed,
+    /// return false if the game is not in progress or already paused
+    async fn pause_quiz<'ctx>(&self, ctx: &Context<'ctx>, quiz_id: ID) -> bool {
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
+        let in_memory_broker: &Arc<InMemoryBroker> = ctx.data_unchecked();
+        let auto_pilots: &AutoPilots = ctx.data_unchecked();
+
+        auto_pilots
+            .pause(in_memory_db, in_memory_broker, &quiz_id)
+            .await
+    }
+
+    /// Accept answers again, the auto-pilot goes on where it was paused. Return false if the
+    /// quiz wasn't paused
+    async fn resume_quiz<'ctx>(&self, ctx: &Context<'ctx>, quiz_id: ID) -> bool {
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
+        let in_memory_broker: &Arc<InMemoryBroker> = ctx.data_unchecked();
+        let auto_pilots: &AutoPilots = ctx.data_unchecked();
+
+        auto_pilots
+            .resume(in_memory_db, in_memory_broker, &quiz_id)
+            .await
+    }
+}
+
+/// Open the question of a quiz at `index` (or the next one) and broadcast it, or end the game
+/// after the last one. `options` are used if the game is started
+pub(crate) async fn open_question(
+    in_memory_db: &Arc<InMemoryDb>,
+    in_memory_broker: &Arc<InMemoryBroker>,
+    quiz_id: &ID,
+    index: Option<usize>,
+    options: Option<GameOptionsInput>,
+) -> async_graphql::Result<Option<Question>> {
+    let _ = in_memory_broker.subscribe_quiz(quiz_id).await;
+    match in_memory_db.open_question(quiz_id, index, options).await? {
+        Some(question) => {
+            in_memory_broker
+                .quiz_event(quiz_id, QuizEvent::Question(question.clone()))
+                .await;
+            Ok(Some(question))
+        }
+        None => {
+            in_memory_broker.unsubscribe_quiz(quiz_id).await;
+            in_memory_broker.unsubscribe_leaderboard(quiz_id).await;
+            in_memory_broker.unsubscribe_achievements(quiz_id).await;
+            Ok(None)
+        }
     }
 }
 
 #[derive(Clone, SimpleObject, Debug)]
+#[graphql(complex)]
 pub(crate) struct Player {
     pub(crate) id: ID,
-    #[graphql(external)]
     pub(crate) quiz_id: ID,
     pub(crate) points: usize,
+    /// Starting at 1 for the best players, players with the same points share the same rank.
+    /// Their order is then decided by the earlier cumulative answer time of the questions they
+    /// answered and then by join order. Null until the player answered a question
+    pub(crate) rank: Option<usize>,
+    /// Number of ranks won (or lost when negative) since the previous question
+    pub(crate) rank_change: Option<i32>,
 }
 
-#[derive(Clone, SimpleObject, Debug, Deserialize)]
+#[ComplexObject]
+impl Player {
+    /// Streaks, accuracy and achievements of the player in the current or last game of the quiz
+    async fn stats<'ctx>(&self, ctx: &Context<'ctx>) -> PlayerStats {
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
+
+        in_memory_db.player_stats(&self.id, &self.quiz_id).await
+    }
+}
+
+impl Player {
+    pub(crate) fn ranked(quiz_id: &ID, player: RankedPlayer) -> Self {
+        Self {
+            id: player.id.clone(),
+            quiz_id: quiz_id.clone(),
+            points: player.points,
+            rank: Some(player.rank),
+            rank_change: player.rank_change,
+        }
+    }
+}
+
+// Fields of the quiz files, a file with a misspelled or former one like `current_question` is
+// rejected rather than loaded without it
+#[derive(Clone, Default, SimpleObject, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[graphql(complex)]
 pub(crate) struct Quiz {
+    #[schemars(with = "String")]
     pub(crate) id: ID,
-    pub(crate) title: String,
+    #[graphql(skip)]
+    pub(crate) title: LocalizedText,
+    /// Questions of the current or last game in the order they are asked, every question of the
+    /// quiz before its first game
+    #[schemars(
+        description = "Every question of the quiz, games ask them in this order unless they're \
+                       shuffled",
+        length(min = 1)
+    )]
     pub(crate) questions: Vec<Question>,
-    #[graphql(skip, default)]
-    pub(crate) current_question: i8,
+    // Every question of the quiz, the questions of a game are drawn from them
+    #[graphql(skip)]
+    #[serde(skip)]
+    pub(crate) bank: Vec<Question>,
+    // Index in `questions`, `None` when there is no game in progress
+    #[graphql(skip)]
+    #[serde(skip)]
+    pub(crate) current_question: Option<usize>,
+    #[graphql(skip)]
+    #[serde(skip)]
+    pub(crate) question_opened_at: Option<DateTime<Utc>>,
+    #[graphql(skip)]
+    #[serde(skip)]
+    pub(crate) paused_at: Option<DateTime<Utc>>,
+    // Players who joined before the game started, in the order they joined
+    #[graphql(skip)]
+    #[serde(skip)]
+    pub(crate) lobby: Vec<ID>,
+    /// Current or last game session of the quiz, to get its report
+    #[serde(skip)]
+    pub(crate) session_id: Option<ID>,
+    /// Code for players to join the game in progress, null when there is none
+    #[serde(skip)]
+    pub(crate) join_code: Option<String>,
+    /// Options the questions of the current or last game were drawn with
+    #[serde(skip)]
+    pub(crate) game_options: Option<GameOptions>,
 }
 
-impl Default for Quiz {
-    fn default() -> Self {
+#[ComplexObject]
+impl Quiz {
+    /// In `locale`, or else in the language of the `Accept-Language` header
+    async fn title<'ctx>(&self, ctx: &Context<'ctx>, locale: Option<String>) -> &str {
+        self.title.resolve(ctx, locale.as_deref())
+    }
+
+    /// Question being played, with how many players answered it. Null when the game isn't started
+    async fn current_question<'ctx>(&self, ctx: &Context<'ctx>) -> Option<CurrentQuestion> {
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
+
+        let question = self.questions.get(self.current_question?)?.clone();
+        let (answered_count, expected_count) = in_memory_db.question_progress(&self.id).await?;
+        Some(CurrentQuestion {
+            question,
+            answered_count,
+            expected_count,
+        })
+    }
+}
+
+#[derive(Clone, SimpleObject, Debug)]
+pub(crate) struct CurrentQuestion {
+    pub(crate) question: Question,
+    pub(crate) answered_count: usize,
+    /// Players who joined the game or answered a question
+    pub(crate) expected_count: usize,
+}
+
+impl Quiz {
+    // Quizzes are read with their questions, which are then the bank of their games
+    pub(crate) fn with_bank(mut self) -> Self {
+        self.bank = self.questions.clone();
+        self
+    }
+
+    /// The quiz as in the quiz file, with every question in file order and no game
+    pub(crate) fn definition(&self) -> Self {
         Self {
-            id: Default::default(),
-            title: Default::default(),
-            questions: Default::default(),
-            current_question: -1,
+            id: self.id.clone(),
+            title: self.title.clone(),
+            questions: self.bank.clone(),
+            ..Default::default()
+        }
+        .with_bank()
+    }
+
+    /// Apply the part of an event about the current question of the quiz
+    fn apply(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::QuizStarted {
+                session_id,
+                join_code,
+                options,
+                ..
+            } => {
+                self.questions = options.draw(&self.bank);
+                self.current_question = None;
+                self.paused_at = None;
+                self.session_id = Some(session_id.clone());
+                self.join_code = join_code.clone();
+                self.game_options = Some(options.clone());
+            }
+            GameEvent::QuestionOpened {
+                question_id, at, ..
+            } => {
+                if let Some(index) = self.questions.iter().position(|q| &q.id == question_id) {
+                    self.current_question = Some(index);
+                }
+                self.question_opened_at = Some(*at);
+                // Opened during a pause, the answer time starts when the game is resumed
+                if self.paused_at.is_some() {
+                    self.paused_at = Some(*at);
+                }
+            }
+            GameEvent::QuestionClosed { .. } | GameEvent::QuestionSkipped { .. } => {
+                self.question_opened_at = None
+            }
+            GameEvent::QuizFinished { .. } => {
+                self.current_question = None;
+                self.question_opened_at = None;
+                self.paused_at = None;
+                // The code can be given to another game
+                self.join_code = None;
+            }
+            GameEvent::QuizPaused { at, .. } => self.paused_at = Some(*at),
+            GameEvent::QuizResumed { at, .. } => {
+                // The time spent paused doesn't count in the answer time
+                if let (Some(paused_at), Some(opened_at)) =
+                    (self.paused_at.take(), self.question_opened_at)
+                {
+                    self.question_opened_at = Some(opened_at + (*at - paused_at));
+                }
+            }
+            GameEvent::PlayerJoined {
+                session_id: None,
+                player_id,
+                ..
+            } => {
+                if !self.lobby.contains(player_id) {
+                    self.lobby.push(player_id.clone());
+                }
+            }
+            GameEvent::AnswerSubmitted { .. }
+            | GameEvent::PlayerJoined { .. }
+            | GameEvent::QuizAdded { .. } => {}
         }
     }
+
+    // Session and id of the current question, if answers are still accepted for it
+    fn opened_question(&self) -> Option<(ID, ID)> {
+        self.question_opened_at?;
+        let question = self.questions.get(self.current_question?)?;
+        Some((self.session_id.clone()?, question.id.clone()))
+    }
 }
 
-#[derive(SimpleObject, Clone, Debug, Deserialize)]
-pub(crate) struct Question {
+#[derive(SimpleObject, Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[graphql(complex)]
+pub struct Question {
+    #[schemars(with = "String")]
     pub(crate) id: ID,
-    pub(crate) title: String,
+    #[graphql(skip)]
+    pub(crate) title: LocalizedText,
+    #[graphql(skip)]
+    #[schemars(length(min = 2))]
     pub(crate) choices: Vec<Choice>,
+    /// ID of the right choice
     #[graphql(skip)]
+    #[schemars(with = "String")]
     pub(crate) good_answer: ID,
+    /// Seconds to answer when the auto-pilot is on, its default time limit is used when null
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) time_limit: Option<u32>,
+    // Seed of the order of the choices for each player, `None` when they all get the same order
+    #[graphql(skip)]
+    #[serde(skip)]
+    pub(crate) choice_seed: Option<u32>,
 }
 
-#[derive(Clone, Default, SimpleObject, Debug, Deserialize)]
+#[ComplexObject]
+impl Question {
+    /// In `locale`, or else in the language of the `Accept-Language` header
+    async fn title<'ctx>(&self, ctx: &Context<'ctx>, locale: Option<String>) -> &str {
+        self.title.resolve(ctx, locale.as_deref())
+    }
+
+    /// In a different order for each player (from the `player` header) when the game shuffles
+    /// them per player, answers are checked against the choice ID so the order doesn't matter
+    async fn choices<'ctx>(&self, ctx: &Context<'ctx>) -> Vec<Choice> {
+        match (self.choice_seed, ctx.data_opt::<ID>()) {
+            (Some(seed), Some(player_id)) => player_choice_order(&self.choices, seed, player_id),
+            _ => self.choices.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Default, SimpleObject, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[graphql(complex)]
 pub(crate) struct Choice {
+    #[schemars(with = "String")]
     pub(crate) id: ID,
-    pub(crate) text: String,
+    #[graphql(skip)]
+    pub(crate) text: LocalizedText,
+}
+
+#[ComplexObject]
+impl Choice {
+    /// In `locale`, or else in the language of the `Accept-Language` header
+    async fn text<'ctx>(&self, ctx: &Context<'ctx>, locale: Option<String>) -> &str {
+        self.text.resolve(ctx, locale.as_deref())
+    }
 }
 
 #[derive(Clone, Default, SimpleObject, Debug)]
-pub(crate) struct Leaderboard {
-    pub(crate) quiz: Quiz,
+#[graphql(complex)]
+pub struct Leaderboard {
+    // The quiz is only resolved when asked for, broadcasts never carry its questions and answers
+    #[graphql(skip)]
+    pub(crate) quiz_id: ID,
     pub(crate) list: Vec<Player>,
+    pub(crate) page_info: PageInfo,
+    // The ranking the list was made from
+    #[graphql(skip)]
+    pub(crate) ranking: Arc<Ranking>,
+}
+
+#[ComplexObject]
+impl Leaderboard {
+    async fn quiz<'ctx>(&self, ctx: &Context<'ctx>) -> async_graphql::Result<Quiz> {
+        let in_memory_db: &Arc<InMemoryDb> = ctx.data_unchecked();
+
+        in_memory_db
+            .get_quiz(&self.quiz_id)
+            .await
+            .ok_or_else(|| async_graphql::Error::new("quiz not found"))
+    }
+
+    /// The `n` best players, for the host screen
+    async fn top(&self, n: usize) -> Vec<Player> {
+        self.ranking
+            .page(None, n)
+            .map(|(page, _)| self.players(page))
+            .unwrap_or_default()
+    }
+
+    /// Rank of the player from the `player` header, with the players right above and below them
+    async fn my_rank<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        #[graphql(default = 2)] neighbours: usize,
+    ) -> Option<PlayerRank> {
+        let player_id: &ID = ctx.data_opt()?;
+        let neighbourhood = self.ranking.neighbourhood(player_id, neighbours)?;
+
+        Some(PlayerRank {
+            rank: neighbourhood.player.rank,
+            player: Player::ranked(&self.quiz_id, neighbourhood.player),
+            above: self.players(neighbourhood.above),
+            below: self.players(neighbourhood.below),
+        })
+    }
+}
+
+impl Leaderboard {
+    fn players(&self, players: Vec<RankedPlayer>) -> Vec<Player> {
+        players
+            .into_iter()
+            .map(|player| Player::ranked(&self.quiz_id, player))
+            .collect()
+    }
+}
+
+#[derive(Clone, Default, SimpleObject, Debug)]
+pub(crate) struct PageInfo {
+    /// Cursor to pass as `after` to get the next page
+    pub(crate) end_cursor: Option<String>,
+    pub(crate) has_next_page: bool,
+}
+
+#[derive(Clone, SimpleObject, Debug)]
+pub(crate) struct PlayerRank {
+    /// Starting at 1 for the best player
+    pub(crate) rank: usize,
+    pub(crate) player: Player,
+    /// Players right above, the closest one last
+    pub(crate) above: Vec<Player>,
+    /// Players right below, the closest one first
+    pub(crate) below: Vec<Player>,
+}
+
+#[derive(Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Badge {
+    /// Five right answers in a row
+    FiveInARow,
+    /// First right answer to a question
+    FastestFinger,
+}
+
+/// What the host did in a game, for the players
+#[derive(Clone, Debug, Union)]
+pub(crate) enum QuizEvent {
+    /// A question was opened
+    Question(Question),
+    QuestionClosed(QuestionClosed),
+    QuestionSkipped(QuestionSkipped),
+    QuizPaused(QuizPaused),
+    QuizResumed(QuizResumed),
+    AllAnswered(AllAnswered),
+}
+
+/// No more answers are accepted for the question
+#[derive(Clone, SimpleObject, Debug)]
+pub(crate) struct QuestionClosed {
+    pub(crate) question_id: ID,
+}
+
+/// The question is closed and its answers don't count
+#[derive(Clone, SimpleObject, Debug)]
+pub(crate) struct QuestionSkipped {
+    pub(crate) question_id: ID,
+}
+
+/// Answers are rejected until the quiz is resumed
+#[derive(Clone, SimpleObject, Debug)]
+pub(crate) struct QuizPaused {
+    pub(crate) quiz_id: ID,
+}
+
+#[derive(Clone, SimpleObject, Debug)]
+pub(crate) struct QuizResumed {
+    pub(crate) quiz_id: ID,
+}
+
+/// Every player of the game answered the question, the host can move on
+#[derive(Clone, SimpleObject, Debug)]
+pub(crate) struct AllAnswered {
+    pub(crate) question_id: ID,
+    pub(crate) answered_count: usize,
+}
+
+/// What an answer did to the game
+pub struct AnswerOutcome {
+    pub(crate) response: Response,
+    // Badges unlocked by the answer
+    pub(crate) badges: Vec<Badge>,
+    // Set on the answer of the last expected player
+    pub(crate) all_answered: Option<AllAnswered>,
+}
+
+#[derive(Clone, SimpleObject, Debug)]
+pub(crate) struct Achievement {
+    pub(crate) player: Player,
+    pub(crate) badge: Badge,
+    /// Question answered when the badge was unlocked
+    pub(crate) question_id: ID,
 }
 
 #[derive(Clone, Default, SimpleObject, Debug)]
-pub(crate) struct Response {
+pub struct Response {
     pub(crate) success: bool,
     pub(crate) right_choice: Choice,
 }
 
+fn load_quizzes() -> HashMap<ID, Quiz> {
+    let quizzes = QuizFile::parse(DATA_FILE)
+        .expect("cannot deserialize the json file containing quizzes")
+        .into_quizzes();
+    quizzes_by_id(quizzes)
+}
+
+fn quizzes_by_id(quizzes: Vec<Quiz>) -> HashMap<ID, Quiz> {
+    quizzes.into_iter().fold(HashMap::new(), |mut acc, quiz| {
+        acc.insert(quiz.id.clone(), quiz.with_bank());
+        acc
+    })
+}
+
 impl Default for InMemoryDb {
     fn default() -> Self {
-        let quizzes: Vec<Quiz> = serde_json::from_str(DATA_FILE)
-            .expect("cannot deserialize the json file containing quizzes");
         Self {
-            quizzes: RwLock::new(quizzes.into_iter().fold(HashMap::new(), |mut acc, quiz| {
-                acc.insert(quiz.id.clone(), quiz);
-                acc
-            })),
-            leaderboard: Default::default(),
+            quizzes: RwLock::new(load_quizzes()),
+            games: Default::default(),
+            archive: Default::default(),
+            event_log: Default::default(),
         }
     }
 }
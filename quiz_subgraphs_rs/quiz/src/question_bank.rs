@@ -0,0 +1,285 @@
+use async_graphql::{Enum, InputObject, SimpleObject, ID};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::{Choice, Question};
+
+#[derive(Enum, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum ChoiceOrder {
+    /// Choices in the order of the quiz file
+    #[default]
+    Fixed,
+    /// Choices shuffled once for the whole game
+    PerGame,
+    /// Choices in a different order for each player, so neighbours can't copy by position
+    PerPlayer,
+}
+
+/// How the questions of a game are drawn from the questions of the quiz
+#[derive(InputObject, Clone, Debug, Default)]
+pub(crate) struct GameOptionsInput {
+    #[graphql(default)]
+    pub(crate) shuffle_questions: bool,
+    #[graphql(default)]
+    pub(crate) choice_order: ChoiceOrder,
+    /// Number of random questions to ask, all of them when null
+    pub(crate) question_count: Option<usize>,
+    /// Seed of the random draws, to play the same game again. A random one when null
+    pub(crate) seed: Option<u32>,
+}
+
+/// How the questions of a game were drawn, the same options give the same questions in the same
+/// order
+#[derive(SimpleObject, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GameOptions {
+    pub(crate) shuffle_questions: bool,
+    pub(crate) choice_order: ChoiceOrder,
+    /// Null when every question was asked
+    pub(crate) question_count: Option<usize>,
+    pub(crate) seed: u32,
+}
+
+impl From<GameOptionsInput> for GameOptions {
+    fn from(input: GameOptionsInput) -> Self {
+        Self {
+            shuffle_questions: input.shuffle_questions,
+            choice_order: input.choice_order,
+            question_count: input.question_count,
+            seed: input
+                .seed
+                .unwrap_or_else(|| Uuid::new_v4().as_u128() as u32),
+        }
+    }
+}
+
+impl GameOptions {
+    /// Questions of the game, drawn from the questions of the quiz
+    pub(crate) fn draw(&self, bank: &[Question]) -> Vec<Question> {
+        let mut rng = Rng::new(self.seed);
+        let mut indexes: Vec<usize> = (0..bank.len()).collect();
+        if let Some(count) = self.question_count.filter(|count| *count < bank.len()) {
+            rng.shuffle(&mut indexes);
+            indexes.truncate(count);
+            // The drawn questions are asked in file order unless they're shuffled too
+            if !self.shuffle_questions {
+                indexes.sort_unstable();
+            }
+        } else if self.shuffle_questions {
+            rng.shuffle(&mut indexes);
+        }
+
+        indexes
+            .into_iter()
+            .map(|index| {
+                let mut question = bank[index].clone();
+                match self.choice_order {
+                    ChoiceOrder::Fixed => {}
+                    ChoiceOrder::PerGame => rng.shuffle(&mut question.choices),
+                    ChoiceOrder::PerPlayer => question.choice_seed = Some(self.seed),
+                }
+                question
+            })
+            .collect()
+    }
+}
+
+/// Choices in the order of a player, always the same for the same game and player
+pub(crate) fn player_choice_order(choices: &[Choice], seed: u32, player_id: &ID) -> Vec<Choice> {
+    // FNV-1a, to get the same order on every server and after a restart
+    let player_hash = player_id
+        .as_bytes()
+        .iter()
+        .fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01B3)
+        });
+    let mut rng = Rng(u64::from(seed) ^ player_hash);
+    let mut choices = choices.to_vec();
+    rng.shuffle(&mut choices);
+    choices
+}
+
+// SplitMix64, enough to shuffle questions and the same everywhere so a seed always gives the
+// same game
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u32) -> Self {
+        Self(seed.into())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Fisher-Yates
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bank(count: usize) -> Vec<Question> {
+        (0..count)
+            .map(|index| Question {
+                id: ID::from(index.to_string()),
+                title: Default::default(),
+                choices: (0..4)
+                    .map(|choice| Choice {
+                        id: ID::from(format!("{index}-{choice}")),
+                        ..Default::default()
+                    })
+                    .collect(),
+                good_answer: ID::from(format!("{index}-0")),
+                time_limit: None,
+                choice_seed: None,
+            })
+            .collect()
+    }
+
+    fn options(shuffle_questions: bool, question_count: Option<usize>, seed: u32) -> GameOptions {
+        GameOptions {
+            shuffle_questions,
+            choice_order: ChoiceOrder::PerGame,
+            question_count,
+            seed,
+        }
+    }
+
+    // Question IDs with the choice IDs of each question, in the order they are asked
+    fn game(questions: &[Question]) -> Vec<(String, Vec<String>)> {
+        questions
+            .iter()
+            .map(|question| {
+                let choices = question.choices.iter().map(|c| c.id.to_string()).collect();
+                (question.id.to_string(), choices)
+            })
+            .collect()
+    }
+
+    fn ids(questions: &[Question]) -> Vec<usize> {
+        questions
+            .iter()
+            .map(|question| question.id.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_game() {
+        let bank = bank(20);
+        let options = options(true, Some(8), 42);
+
+        assert_eq!(game(&options.draw(&bank)), game(&options.draw(&bank)));
+        let other_seed = GameOptions {
+            seed: 43,
+            ..options.clone()
+        };
+        assert_ne!(game(&options.draw(&bank)), game(&other_seed.draw(&bank)));
+    }
+
+    #[test]
+    fn drawn_questions_keep_the_file_order_unless_shuffled() {
+        let bank = bank(20);
+
+        let drawn = ids(&options(false, Some(8), 42).draw(&bank));
+        assert_eq!(drawn.len(), 8);
+        assert!(drawn.windows(2).all(|pair| pair[0] < pair[1]), "{drawn:?}");
+
+        let shuffled = ids(&options(true, Some(8), 42).draw(&bank));
+        let mut sorted = shuffled.clone();
+        sorted.sort_unstable();
+        // The same questions are drawn, only their order changes
+        assert_eq!(sorted, drawn);
+        assert_ne!(shuffled, drawn);
+    }
+
+    #[test]
+    fn every_question_is_asked_without_question_count() {
+        let bank = bank(5);
+
+        assert_eq!(ids(&options(false, None, 42).draw(&bank)), [0, 1, 2, 3, 4]);
+        assert_eq!(
+            ids(&options(false, Some(10), 42).draw(&bank)),
+            [0, 1, 2, 3, 4]
+        );
+        let mut shuffled = ids(&options(true, None, 42).draw(&bank));
+        shuffled.sort_unstable();
+        assert_eq!(shuffled, [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn fixed_choices_keep_their_order() {
+        let bank = bank(3);
+        let options = GameOptions {
+            choice_order: ChoiceOrder::Fixed,
+            ..options(false, None, 42)
+        };
+
+        assert_eq!(game(&options.draw(&bank)), game(&bank));
+    }
+
+    fn choice_ids(choices: &[Choice]) -> Vec<String> {
+        choices.iter().map(|choice| choice.id.to_string()).collect()
+    }
+
+    #[test]
+    fn players_keep_their_choice_order() {
+        let choices = bank(1).remove(0).choices;
+        let alice = ID::from("alice");
+
+        let order = choice_ids(&player_choice_order(&choices, 42, &alice));
+        assert_eq!(
+            choice_ids(&player_choice_order(&choices, 42, &alice)),
+            order
+        );
+        // Only the order changes, the choices keep their IDs
+        let mut sorted = order.clone();
+        sorted.sort();
+        assert_eq!(sorted, choice_ids(&choices));
+    }
+
+    #[test]
+    fn players_get_different_choice_orders() {
+        let choices = bank(1).remove(0).choices;
+        let orders: std::collections::HashSet<Vec<String>> = (0..10)
+            .map(|player| {
+                choice_ids(&player_choice_order(
+                    &choices,
+                    42,
+                    &ID::from(player.to_string()),
+                ))
+            })
+            .collect();
+
+        assert!(orders.len() > 1);
+    }
+
+    #[test]
+    fn per_player_choices_are_drawn_with_the_seed_of_the_game() {
+        let bank = bank(3);
+        let options = GameOptions {
+            choice_order: ChoiceOrder::PerPlayer,
+            ..options(false, None, 42)
+        };
+
+        for (question, file_question) in options.draw(&bank).iter().zip(&bank) {
+            assert_eq!(question.choice_seed, Some(42));
+            // Each player gets their order when the choices are resolved
+            assert_eq!(
+                choice_ids(&question.choices),
+                choice_ids(&file_question.choices)
+            );
+        }
+    }
+}
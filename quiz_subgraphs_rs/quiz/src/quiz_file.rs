@@ -0,0 +1,206 @@
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::Path;
+
+use schemars::JsonSchema;
+
+use crate::models::{Quiz, DATA_FILE};
+
+/// A list of quizzes, or a single quiz like the exported ones
+#[derive(JsonSchema)]
+#[serde(untagged)]
+pub(crate) enum QuizFile {
+    Quizzes(Vec<Quiz>),
+    Quiz(Box<Quiz>),
+}
+
+impl QuizFile {
+    // Rather than deserializing an untagged enum, which can't tell where the mistake is
+    pub(crate) fn parse(content: &str) -> serde_json::Result<Self> {
+        if content.trim_start().starts_with('[') {
+            serde_json::from_str(content).map(QuizFile::Quizzes)
+        } else {
+            serde_json::from_str(content).map(QuizFile::Quiz)
+        }
+    }
+
+    pub(crate) fn into_quizzes(self) -> Vec<Quiz> {
+        match self {
+            QuizFile::Quizzes(quizzes) => quizzes,
+            QuizFile::Quiz(quiz) => vec![*quiz],
+        }
+    }
+}
+
+/// Mistakes in quizzes that deserialize fine but can't be played properly
+pub(crate) fn check(quizzes: &[Quiz]) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut quiz_ids = HashSet::new();
+    for quiz in quizzes {
+        let quiz_name = format!("quiz {:?}", quiz.id.as_str());
+        if !quiz_ids.insert(&quiz.id) {
+            problems.push(format!("{quiz_name}: duplicate quiz id"));
+        }
+        if quiz.questions.is_empty() {
+            problems.push(format!("{quiz_name}: no questions"));
+        }
+
+        let mut question_ids = HashSet::new();
+        for question in &quiz.questions {
+            let question_name = format!("{quiz_name}, question {:?}", question.id.as_str());
+            if !question_ids.insert(&question.id) {
+                problems.push(format!("{question_name}: duplicate question id"));
+            }
+            match question.choices.len() {
+                0 => problems.push(format!("{question_name}: no choices")),
+                1 => problems.push(format!("{question_name}: fewer than two choices")),
+                _ => {}
+            }
+            let mut choice_ids = HashSet::new();
+            for choice in &question.choices {
+                if !choice_ids.insert(&choice.id) {
+                    problems.push(format!(
+                        "{question_name}, choice {:?}: duplicate choice id",
+                        choice.id.as_str()
+                    ));
+                }
+            }
+            if !choice_ids.contains(&question.good_answer) {
+                problems.push(format!(
+                    "{question_name}: good_answer {:?} is not one of the choices",
+                    question.good_answer.as_str()
+                ));
+            }
+        }
+    }
+    problems
+}
+
+/// JSON Schema of the quiz files like `data.json`
+pub fn quiz_file_json_schema() -> String {
+    serde_json::to_string_pretty(&schemars::schema_for!(QuizFile))
+        .expect("the JSON Schema of the quiz files can't be serialized")
+}
+
+/// Read a quiz file and check its quizzes, or return the problems found
+pub(crate) fn read_quiz_file(path: &Path) -> Result<Vec<Quiz>, Vec<String>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| vec![format!("cannot read {path:?}: {err}")])?;
+    let quizzes = QuizFile::parse(&content)
+        .map_err(|err| vec![format!("invalid quiz file: {err}")])?
+        .into_quizzes();
+
+    let problems = check(&quizzes);
+    if problems.is_empty() {
+        Ok(quizzes)
+    } else {
+        Err(problems)
+    }
+}
+
+/// Check a quiz file, return the number of quizzes in it or the problems found
+pub fn validate_quiz_file(path: &Path) -> Result<usize, Vec<String>> {
+    read_quiz_file(path).map(|quizzes| quizzes.len())
+}
+
+/// Write the built-in quizzes to a new quiz file, to edit them and serve them with `--data`
+pub fn seed_quiz_file(path: &Path) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?;
+    file.write_all(DATA_FILE.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn question(id: &str, choice_ids: &[&str], good_answer: &str) -> Value {
+        let choices: Vec<Value> = choice_ids
+            .iter()
+            .map(|choice_id| json!({ "id": choice_id, "text": "Choice" }))
+            .collect();
+        json!({ "id": id, "title": "Question", "choices": choices, "good_answer": good_answer })
+    }
+
+    fn quiz(id: &str, questions: Vec<Value>) -> Value {
+        json!({ "id": id, "title": "Quiz", "questions": questions })
+    }
+
+    fn check_file(file: Value) -> Vec<String> {
+        check(&QuizFile::parse(&file.to_string()).unwrap().into_quizzes())
+    }
+
+    #[test]
+    fn built_in_quizzes_have_no_problems() {
+        let quizzes = QuizFile::parse(DATA_FILE).unwrap().into_quizzes();
+        assert_eq!(check(&quizzes), Vec::<String>::new());
+    }
+
+    #[test]
+    fn single_quizzes_are_checked_like_lists() {
+        let problems = check_file(quiz("0", vec![]));
+        assert_eq!(problems, [r#"quiz "0": no questions"#]);
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let mut file = quiz("0", vec![question("0", &["0", "1"], "0")]);
+        file["current_question"] = json!(0);
+        let err = QuizFile::parse(&file.to_string()).err().unwrap();
+        assert!(
+            err.to_string().contains("unknown field `current_question`"),
+            "{err}"
+        );
+
+        let mut file = quiz("0", vec![question("0", &["0", "1"], "0")]);
+        file["questions"][0]["choices"][1]["correct"] = json!(true);
+        assert!(QuizFile::parse(&file.to_string()).is_err());
+    }
+
+    #[test]
+    fn duplicate_ids_are_reported() {
+        let problems = check_file(json!([
+            quiz(
+                "0",
+                vec![
+                    question("0", &["0", "1"], "0"),
+                    question("0", &["0", "1", "1"], "1"),
+                ]
+            ),
+            quiz("0", vec![question("0", &["0", "1"], "0")]),
+        ]));
+        assert_eq!(
+            problems,
+            [
+                r#"quiz "0", question "0": duplicate question id"#,
+                r#"quiz "0", question "0", choice "1": duplicate choice id"#,
+                r#"quiz "0": duplicate quiz id"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn questions_need_two_choices_with_the_good_answer() {
+        let problems = check_file(json!([quiz(
+            "0",
+            vec![
+                question("0", &[], "0"),
+                question("1", &["0"], "0"),
+                question("2", &["0", "1"], "2"),
+            ]
+        )]));
+        assert_eq!(
+            problems,
+            [
+                r#"quiz "0", question "0": no choices"#,
+                r#"quiz "0", question "0": good_answer "0" is not one of the choices"#,
+                r#"quiz "0", question "1": fewer than two choices"#,
+                r#"quiz "0", question "2": good_answer "2" is not one of the choices"#,
+            ]
+        );
+    }
+}
@@ -0,0 +1,375 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
+use std::time::Duration;
+
+use async_graphql::ID;
+
+/// Position of a player in the ranking.
+///
+/// Players are sorted by points, ties are broken by the earlier cumulative answer time and then
+/// by join order, so the order never changes between two broadcasts. The answer time only adds
+/// up the questions a player answered, skipping one doesn't cost any time. Players with the same
+/// points still share the same rank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct RankKey {
+    points: Reverse<usize>,
+    answer_time: Duration,
+    joined: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Score {
+    points: usize,
+    // Time taken to answer every question so far
+    answer_time: Duration,
+    joined: usize,
+}
+
+impl Score {
+    fn key(&self) -> RankKey {
+        RankKey {
+            points: Reverse(self.points),
+            answer_time: self.answer_time,
+            joined: self.joined,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct RankedPlayer<'a> {
+    pub(crate) id: &'a ID,
+    pub(crate) points: usize,
+    // Starting at 1 for the best players
+    pub(crate) rank: usize,
+    // Positive when the player moved up since the previous question
+    pub(crate) rank_change: Option<i32>,
+}
+
+/// Where a player stands in the ranking, with the players right above and below them
+#[derive(Debug)]
+pub(crate) struct Neighbourhood<'a> {
+    pub(crate) player: RankedPlayer<'a>,
+    // The closest player last
+    pub(crate) above: Vec<RankedPlayer<'a>>,
+    // The closest player first
+    pub(crate) below: Vec<RankedPlayer<'a>>,
+}
+
+/// Scores of every player in a quiz, kept sorted as answers come in so the leaderboard
+/// never has to be sorted again.
+#[derive(Default, Debug, Clone)]
+pub(crate) struct Ranking {
+    scores: HashMap<ID, Score>,
+    // From the best player to the worst one
+    ranked: BTreeMap<RankKey, ID>,
+    // Number of players by points, to know a rank without counting every player above
+    points_count: BTreeMap<usize, usize>,
+    // Rank of the players when the current question was opened
+    previous_ranks: HashMap<ID, usize>,
+    // Position of the players in the order they joined the game
+    join_order: HashMap<ID, usize>,
+}
+
+impl Ranking {
+    /// Remember when a player joined, before they show up in the ranking with their first answer
+    pub(crate) fn join(&mut self, player_id: &ID) {
+        let joined = self.join_order.len();
+        self.join_order.entry(player_id.clone()).or_insert(joined);
+    }
+
+    /// Add points and answer time to a player, registering them if it's their first answer.
+    /// Players who answer without joining first join with that answer
+    pub(crate) fn add_points(&mut self, player_id: &ID, incr: usize, answer_time: Duration) {
+        self.join(player_id);
+        let joined = self.join_order[player_id];
+        self.scores.entry(player_id.clone()).or_insert(Score {
+            points: 0,
+            answer_time: Duration::ZERO,
+            joined,
+        });
+        self.update(player_id, |score| {
+            score.points += incr;
+            score.answer_time += answer_time;
+        });
+    }
+
+    /// Take back points and answer time given by `add_points`, the player stays in the ranking
+    pub(crate) fn remove_points(&mut self, player_id: &ID, decr: usize, answer_time: Duration) {
+        self.update(player_id, |score| {
+            score.points = score.points.saturating_sub(decr);
+            score.answer_time = score.answer_time.saturating_sub(answer_time);
+        });
+    }
+
+    // Change the score of a player, keeping the ranking sorted
+    fn update(&mut self, player_id: &ID, change: impl FnOnce(&mut Score)) {
+        let Some(score) = self.scores.get_mut(player_id) else {
+            return;
+        };
+        if self.ranked.remove(&score.key()).is_some() {
+            if let Some(count) = self.points_count.get_mut(&score.points) {
+                *count -= 1;
+                if *count == 0 {
+                    self.points_count.remove(&score.points);
+                }
+            }
+        }
+
+        change(score);
+        self.ranked.insert(score.key(), player_id.clone());
+        *self.points_count.entry(score.points).or_default() += 1;
+    }
+
+    pub(crate) fn get(&self, player_id: &ID) -> Option<RankedPlayer<'_>> {
+        let score = self.scores.get(player_id)?;
+        let player_id = self.ranked.get(&score.key())?;
+        Some(self.ranked_player(player_id, score.points))
+    }
+
+    /// Remember the current ranks, `rank_change` is relative to them
+    pub(crate) fn snapshot_ranks(&mut self) {
+        let ranks = self
+            .iter()
+            .map(|player| (player.id.clone(), player.rank))
+            .collect();
+        self.previous_ranks = ranks;
+    }
+
+    /// Players from the best to the worst one
+    pub(crate) fn iter(&self) -> impl Iterator<Item = RankedPlayer<'_>> {
+        self.ranked
+            .iter()
+            .map(|(key, player_id)| self.ranked_player(player_id, key.points.0))
+    }
+
+    /// At most `first` players ranked right after the `after` player (or from the best one),
+    /// and whether there are more players after them
+    pub(crate) fn page(
+        &self,
+        after: Option<&ID>,
+        first: usize,
+    ) -> Option<(Vec<RankedPlayer<'_>>, bool)> {
+        let start = match after {
+            Some(player_id) => Bound::Excluded(self.scores.get(player_id)?.key()),
+            None => Bound::Unbounded,
+        };
+        let mut players = self
+            .ranked
+            .range((start, Bound::Unbounded))
+            .map(|(key, player_id)| self.ranked_player(player_id, key.points.0));
+        let page = players.by_ref().take(first).collect();
+
+        Some((page, players.next().is_some()))
+    }
+
+    /// Rank of a player with up to `neighbours` players above and below them
+    pub(crate) fn neighbourhood(
+        &self,
+        player_id: &ID,
+        neighbours: usize,
+    ) -> Option<Neighbourhood<'_>> {
+        let player = self.get(player_id)?;
+        let key = self.scores.get(player_id)?.key();
+        let mut above: Vec<_> = self
+            .ranked
+            .range(..key)
+            .rev()
+            .take(neighbours)
+            .map(|(key, player_id)| self.ranked_player(player_id, key.points.0))
+            .collect();
+        above.reverse();
+        let below = self
+            .ranked
+            .range((Bound::Excluded(key), Bound::Unbounded))
+            .take(neighbours)
+            .map(|(key, player_id)| self.ranked_player(player_id, key.points.0))
+            .collect();
+
+        Some(Neighbourhood {
+            player,
+            above,
+            below,
+        })
+    }
+
+    fn ranked_player<'a>(&'a self, player_id: &'a ID, points: usize) -> RankedPlayer<'a> {
+        let rank = self.rank(points);
+        RankedPlayer {
+            id: player_id,
+            points,
+            rank,
+            rank_change: self
+                .previous_ranks
+                .get(player_id)
+                .map(|previous_rank| *previous_rank as i32 - rank as i32),
+        }
+    }
+
+    // Players with the same points share the same rank
+    fn rank(&self, points: usize) -> usize {
+        self.points_count
+            .range((Bound::Excluded(points), Bound::Unbounded))
+            .map(|(_, count)| count)
+            .sum::<usize>()
+            + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranks(ranking: &Ranking) -> Vec<(&str, usize, usize)> {
+        ranking
+            .iter()
+            .map(|player| (player.id.as_str(), player.points, player.rank))
+            .collect()
+    }
+
+    #[test]
+    fn ties_share_a_rank_and_are_ordered_by_answer_time() {
+        let mut ranking = Ranking::default();
+        ranking.add_points(&ID::from("slow"), 1, Duration::from_secs(5));
+        ranking.add_points(&ID::from("fast"), 1, Duration::from_secs(2));
+        ranking.add_points(&ID::from("wrong"), 0, Duration::from_secs(1));
+
+        assert_eq!(
+            ranks(&ranking),
+            [("fast", 1, 1), ("slow", 1, 1), ("wrong", 0, 3)]
+        );
+    }
+
+    #[test]
+    fn ties_on_answer_time_are_ordered_by_join_order() {
+        let mut ranking = Ranking::default();
+        ranking.join(&ID::from("first"));
+        ranking.join(&ID::from("second"));
+        ranking.add_points(&ID::from("second"), 1, Duration::from_secs(2));
+        ranking.add_points(&ID::from("late"), 1, Duration::from_secs(2));
+        ranking.add_points(&ID::from("first"), 1, Duration::from_secs(2));
+
+        assert_eq!(
+            ranks(&ranking),
+            [("first", 1, 1), ("second", 1, 1), ("late", 1, 1)]
+        );
+    }
+
+    #[test]
+    fn rank_counts_the_players_with_more_points() {
+        let mut ranking = Ranking::default();
+        for (player, points) in [("a", 3), ("b", 3), ("c", 2), ("d", 1), ("e", 1)] {
+            ranking.add_points(&ID::from(player), points, Duration::ZERO);
+        }
+
+        assert_eq!(ranking.rank(3), 1);
+        assert_eq!(ranking.rank(2), 3);
+        assert_eq!(ranking.rank(1), 4);
+        assert_eq!(ranking.rank(0), 6);
+    }
+
+    #[test]
+    fn removed_points_move_a_player_back() {
+        let mut ranking = Ranking::default();
+        ranking.add_points(&ID::from("a"), 2, Duration::from_secs(1));
+        ranking.add_points(&ID::from("b"), 1, Duration::from_secs(1));
+        ranking.remove_points(&ID::from("a"), 2, Duration::from_secs(1));
+
+        assert_eq!(ranks(&ranking), [("b", 1, 1), ("a", 0, 2)]);
+    }
+
+    #[test]
+    fn rank_change_is_relative_to_the_last_snapshot() {
+        let mut ranking = Ranking::default();
+        let (a, b, c) = (ID::from("a"), ID::from("b"), ID::from("c"));
+        ranking.add_points(&a, 2, Duration::ZERO);
+        ranking.add_points(&b, 1, Duration::ZERO);
+        assert_eq!(ranking.get(&a).unwrap().rank_change, None);
+
+        ranking.snapshot_ranks();
+        ranking.add_points(&b, 2, Duration::ZERO);
+        ranking.add_points(&c, 1, Duration::ZERO);
+
+        assert_eq!(ranking.get(&a).unwrap().rank_change, Some(-1));
+        assert_eq!(ranking.get(&b).unwrap().rank_change, Some(1));
+        // Not ranked when the snapshot was taken
+        assert_eq!(ranking.get(&c).unwrap().rank_change, None);
+    }
+
+    // Players "a" to "f" with 3, 2, 2, 2, 2 and 1 points, "b" to "e" tied in join order
+    fn ranking_with_ties() -> Ranking {
+        let mut ranking = Ranking::default();
+        for (player, points) in [("a", 3), ("b", 2), ("c", 2), ("d", 2), ("e", 2), ("f", 1)] {
+            ranking.add_points(&ID::from(player), points, Duration::ZERO);
+        }
+        ranking
+    }
+
+    fn ids(players: &[RankedPlayer]) -> Vec<String> {
+        players.iter().map(|player| player.id.to_string()).collect()
+    }
+
+    #[test]
+    fn pages_go_through_tied_players_once() {
+        let ranking = ranking_with_ties();
+
+        let (page, has_next_page) = ranking.page(None, 2).unwrap();
+        assert_eq!(ids(&page), ["a", "b"]);
+        assert!(has_next_page);
+        let (page, has_next_page) = ranking.page(Some(&ID::from("b")), 2).unwrap();
+        assert_eq!(ids(&page), ["c", "d"]);
+        assert_eq!(
+            page.iter().map(|player| player.rank).collect::<Vec<_>>(),
+            [2, 2]
+        );
+        assert!(has_next_page);
+        let (page, has_next_page) = ranking.page(Some(&ID::from("d")), 2).unwrap();
+        assert_eq!(ids(&page), ["e", "f"]);
+        assert!(!has_next_page);
+    }
+
+    #[test]
+    fn last_pages_have_no_next_page() {
+        let ranking = ranking_with_ties();
+
+        let (page, has_next_page) = ranking.page(None, 6).unwrap();
+        assert_eq!(page.len(), 6);
+        assert!(!has_next_page);
+        let (page, has_next_page) = ranking.page(Some(&ID::from("f")), 2).unwrap();
+        assert!(page.is_empty());
+        assert!(!has_next_page);
+    }
+
+    #[test]
+    fn unknown_cursors_are_invalid() {
+        let ranking = ranking_with_ties();
+
+        assert!(ranking.page(Some(&ID::from("unknown")), 2).is_none());
+    }
+
+    #[test]
+    fn neighbourhoods_stop_at_the_ends_of_the_ranking() {
+        let ranking = ranking_with_ties();
+
+        let first = ranking.neighbourhood(&ID::from("a"), 2).unwrap();
+        assert_eq!(first.player.rank, 1);
+        assert!(first.above.is_empty());
+        assert_eq!(ids(&first.below), ["b", "c"]);
+
+        let last = ranking.neighbourhood(&ID::from("f"), 2).unwrap();
+        assert_eq!(last.player.rank, 6);
+        assert_eq!(ids(&last.above), ["d", "e"]);
+        assert!(last.below.is_empty());
+    }
+
+    #[test]
+    fn neighbours_are_ordered_around_the_player() {
+        let ranking = ranking_with_ties();
+
+        let middle = ranking.neighbourhood(&ID::from("c"), 2).unwrap();
+        assert_eq!(middle.player.rank, 2);
+        assert_eq!(ids(&middle.above), ["a", "b"]);
+        assert_eq!(ids(&middle.below), ["d", "e"]);
+        assert!(ranking.neighbourhood(&ID::from("unknown"), 2).is_none());
+    }
+}
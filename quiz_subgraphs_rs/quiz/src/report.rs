@@ -0,0 +1,191 @@
+use async_graphql::{SimpleObject, ID};
+use serde::Serialize;
+
+use crate::history::GameHistory;
+use crate::models::Quiz;
+use crate::ranking::Ranking;
+
+/// What happened during a game session, for the host once the game is over
+#[derive(Clone, Debug, SimpleObject, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GameReport {
+    pub(crate) session_id: ID,
+    pub(crate) quiz_id: ID,
+    pub(crate) quiz_title: String,
+    /// Questions in the order they were asked
+    pub(crate) questions: Vec<QuestionReport>,
+    /// Question with the lowest accuracy, the slowest to answer on ties
+    pub(crate) hardest_question: Option<QuestionReport>,
+    /// Players from the best to the worst one
+    pub(crate) players: Vec<PlayerReport>,
+}
+
+#[derive(Clone, Debug, SimpleObject, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QuestionReport {
+    pub(crate) question_id: ID,
+    pub(crate) title: String,
+    pub(crate) answer_count: usize,
+    pub(crate) right_answer_count: usize,
+    /// Share of right answers, between 0 and 1. Null when nobody answered
+    pub(crate) accuracy: Option<f64>,
+    /// Average time to answer in milliseconds. Null when nobody answered
+    pub(crate) average_answer_time: Option<u64>,
+}
+
+#[derive(Clone, Debug, SimpleObject, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PlayerReport {
+    pub(crate) player_id: ID,
+    pub(crate) rank: usize,
+    pub(crate) points: usize,
+    pub(crate) accuracy: Option<f64>,
+    /// Average time to answer in milliseconds
+    pub(crate) average_response_time: Option<u64>,
+    pub(crate) longest_streak: usize,
+    /// Answers in the order they were given
+    pub(crate) answers: Vec<PlayerAnswerReport>,
+}
+
+#[derive(Clone, Debug, SimpleObject, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PlayerAnswerReport {
+    pub(crate) question_id: ID,
+    pub(crate) correct: bool,
+    /// Time to answer in milliseconds
+    pub(crate) answer_time: u64,
+}
+
+impl GameReport {
+    pub(crate) fn new(
+        session_id: &ID,
+        quiz: &Quiz,
+        ranking: &Ranking,
+        history: &GameHistory,
+    ) -> Self {
+        let questions: Vec<QuestionReport> = history
+            .questions()
+            .iter()
+            .map(|asked| {
+                let question_id = &asked.id;
+                let title = quiz
+                    .questions
+                    .iter()
+                    .find(|question| &question.id == question_id)
+                    .map(|question| question.title.default_text().to_owned())
+                    .unwrap_or_default();
+                let (mut answer_count, mut right_answer_count, mut total_time) = (0, 0, 0);
+                for answer in history.answers_to(question_id) {
+                    answer_count += 1;
+                    right_answer_count += answer.correct as usize;
+                    total_time += answer.answer_time.as_millis() as u64;
+                }
+
+                QuestionReport {
+                    question_id: question_id.clone(),
+                    title,
+                    answer_count,
+                    right_answer_count,
+                    accuracy: (answer_count > 0)
+                        .then(|| right_answer_count as f64 / answer_count as f64),
+                    average_answer_time: (answer_count > 0)
+                        .then(|| total_time / answer_count as u64),
+                }
+            })
+            .collect();
+        let hardest_question = questions
+            .iter()
+            .filter(|question| question.accuracy.is_some())
+            .min_by(|a, b| {
+                a.accuracy
+                    .partial_cmp(&b.accuracy)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then(b.average_answer_time.cmp(&a.average_answer_time))
+            })
+            .cloned();
+        let players = ranking
+            .iter()
+            .map(|player| {
+                let player_history = history.player(player.id);
+                let stats = player_history
+                    .map(|player| player.stats())
+                    .unwrap_or_default();
+
+                PlayerReport {
+                    player_id: player.id.clone(),
+                    rank: player.rank,
+                    points: player.points,
+                    accuracy: stats.accuracy,
+                    average_response_time: stats.average_response_time,
+                    longest_streak: stats.longest_streak,
+                    answers: player_history
+                        .map(|player| {
+                            player
+                                .answers
+                                .iter()
+                                .map(|answer| PlayerAnswerReport {
+                                    question_id: answer.question_id.clone(),
+                                    correct: answer.correct,
+                                    answer_time: answer.answer_time.as_millis() as u64,
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        Self {
+            session_id: session_id.clone(),
+            quiz_id: quiz.id.clone(),
+            quiz_title: quiz.title.default_text().to_owned(),
+            questions,
+            hardest_question,
+            players,
+        }
+    }
+
+    /// One line per answer of each player, with the player and question details repeated
+    /// so it can be filtered or pivoted in a spreadsheet
+    pub(crate) fn to_csv(&self) -> Result<String, csv::Error> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record([
+            "rank",
+            "player_id",
+            "points",
+            "question_id",
+            "question_title",
+            "question_accuracy",
+            "correct",
+            "answer_time_ms",
+        ])?;
+        for player in &self.players {
+            for answer in &player.answers {
+                let question = self
+                    .questions
+                    .iter()
+                    .find(|question| question.question_id == answer.question_id);
+                writer.write_record([
+                    player.rank.to_string(),
+                    player.player_id.to_string(),
+                    player.points.to_string(),
+                    answer.question_id.to_string(),
+                    question
+                        .map(|question| question.title.clone())
+                        .unwrap_or_default(),
+                    question
+                        .and_then(|question| question.accuracy)
+                        .map(|accuracy| accuracy.to_string())
+                        .unwrap_or_default(),
+                    answer.correct.to_string(),
+                    answer.answer_time.to_string(),
+                ])?;
+            }
+        }
+
+        let csv = writer
+            .into_inner()
+            .map_err(|err| csv::Error::from(err.into_error()))?;
+        Ok(String::from_utf8(csv).expect("every field is valid UTF-8"))
+    }
+}
@@ -0,0 +1,328 @@
+mod harness;
+
+use serde_json::{json, Value};
+
+use workshop_checker::Subgraph;
+
+// Quiz "0" of `data.json`, with two questions
+const QUIZ_ID: &str = "0";
+
+const CREATE_PLAYER: &str = "mutation($userName: String!, $quizId: ID, $code: String) {
+    createPlayer(userName: $userName, quizId: $quizId, code: $code) { id }
+}";
+const NEXT_QUESTION: &str = "mutation($quizId: ID!) { nextQuestion(quizId: $quizId) { id } }";
+const ANSWER: &str = "mutation($quizId: ID!, $questionId: ID!, $choiceId: ID) {
+    answer(quizId: $quizId, questionId: $questionId, choiceId: $choiceId) { success }
+}";
+
+async fn create_player(player: &Subgraph, variables: Value) -> String {
+    let data = player
+        .execute(CREATE_PLAYER, variables, None)
+        .await
+        .unwrap();
+    data["createPlayer"]["id"].as_str().unwrap().to_string()
+}
+
+async fn next_question(quiz: &Subgraph) -> Value {
+    let data = quiz
+        .execute(NEXT_QUESTION, json!({ "quizId": QUIZ_ID }), None)
+        .await
+        .unwrap();
+    data["nextQuestion"]["id"].clone()
+}
+
+async fn answer(quiz: &Subgraph, player_id: &str, question_id: &str, choice_id: &str) -> bool {
+    let variables = json!({ "quizId": QUIZ_ID, "questionId": question_id, "choiceId": choice_id });
+    let data = quiz
+        .execute(ANSWER, variables, Some(player_id))
+        .await
+        .unwrap();
+    data["answer"]["success"].as_bool().unwrap()
+}
+
+async fn join_code(quiz: &Subgraph) -> Value {
+    let data = quiz
+        .execute("{ allQuizzes { id joinCode } }", json!({}), None)
+        .await
+        .unwrap();
+    data["allQuizzes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|quiz| quiz["id"] == QUIZ_ID)
+        .unwrap()["joinCode"]
+        .clone()
+}
+
+#[tokio::test]
+async fn full_game() {
+    let quiz = harness::quiz();
+    let player = harness::player(&quiz);
+    let quiz_id = json!({ "quizId": QUIZ_ID });
+
+    let mut lobby = player
+        .subscribe(
+            "subscription($quizId: ID!) {
+                lobbyEventsForAQuiz(quizId: $quizId) {
+                    __typename
+                    ... on LobbySnapshot { players { name } }
+                    ... on PlayerJoined { player { name } }
+                }
+            }",
+            quiz_id.clone(),
+            None,
+        )
+        .await
+        .unwrap();
+    let questions = quiz
+        .subscribe(
+            "subscription($quizId: ID!) { newQuestion(quizId: $quizId) { id } }",
+            quiz_id.clone(),
+            None,
+        )
+        .await
+        .unwrap();
+    let leaderboard = quiz
+        .subscribe(
+            "subscription($id: ID!) { leaderboardForQuiz(id: $id) { list { id points rank } } }",
+            json!({ "id": QUIZ_ID }),
+            None,
+        )
+        .await
+        .unwrap();
+
+    let alice = create_player(&player, json!({ "userName": "alice", "quizId": QUIZ_ID })).await;
+    assert_eq!(next_question(&quiz).await, "0");
+    // Once the game is started, players can join with its code
+    let code = join_code(&quiz).await;
+    let bob = create_player(&player, json!({ "userName": "bob", "code": code })).await;
+
+    assert!(answer(&quiz, &alice, "0", "1").await);
+    assert!(!answer(&quiz, &bob, "0", "2").await);
+    assert_eq!(next_question(&quiz).await, "1");
+    assert!(answer(&quiz, &alice, "1", "0").await);
+    assert!(answer(&quiz, &bob, "1", "0").await);
+    // After the last question the game is over
+    assert_eq!(next_question(&quiz).await, Value::Null);
+
+    assert_eq!(
+        lobby.next().await.unwrap().unwrap(),
+        json!({ "lobbyEventsForAQuiz": { "__typename": "LobbySnapshot", "players": [] } })
+    );
+    for name in ["alice", "bob"] {
+        assert_eq!(
+            lobby.next().await.unwrap().unwrap(),
+            json!({ "lobbyEventsForAQuiz": { "__typename": "PlayerJoined", "player": { "name": name } } })
+        );
+    }
+    assert_eq!(
+        questions.collect().await.unwrap(),
+        [
+            json!({ "newQuestion": { "id": "0" } }),
+            json!({ "newQuestion": { "id": "1" } })
+        ]
+    );
+    // Answers coming in together are in the same update, only the last one is known for sure
+    let leaderboards = leaderboard.collect().await.unwrap();
+    assert_eq!(
+        leaderboards.last().unwrap(),
+        &json!({ "leaderboardForQuiz": { "list": [
+            { "id": alice, "points": 2, "rank": 1 },
+            { "id": bob, "points": 1, "rank": 2 },
+        ] } })
+    );
+}
+
+#[tokio::test]
+async fn players_answer_a_question_once() {
+    let quiz = harness::quiz();
+    next_question(&quiz).await;
+
+    assert!(answer(&quiz, "alice", "0", "1").await);
+    let variables = json!({ "quizId": QUIZ_ID, "questionId": "0", "choiceId": "1" });
+    assert_eq!(
+        quiz.execute(ANSWER, variables, Some("alice"))
+            .await
+            .unwrap_err(),
+        "cannot answer"
+    );
+
+    let data = quiz
+        .execute(
+            "query($id: ID!) { leaderboardForQuiz(id: $id) { list { points } } }",
+            json!({ "id": QUIZ_ID }),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(data["leaderboardForQuiz"]["list"], json!([{ "points": 1 }]));
+}
+
+#[tokio::test]
+async fn reports_outlive_their_game() {
+    let quiz = harness::quiz();
+    next_question(&quiz).await;
+    let data = quiz
+        .execute("{ allQuizzes { id sessionId } }", json!({}), None)
+        .await
+        .unwrap();
+    let session_id = data["allQuizzes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|quiz| quiz["id"] == QUIZ_ID)
+        .unwrap()["sessionId"]
+        .clone();
+    assert!(answer(&quiz, "alice", "0", "1").await);
+    next_question(&quiz).await;
+    assert_eq!(next_question(&quiz).await, Value::Null);
+
+    // The next game replaces the finished one, whose report is then read from the archive
+    assert_eq!(next_question(&quiz).await, "0");
+    let data = quiz
+        .execute(
+            "query($sessionId: ID!) { gameReport(sessionId: $sessionId) { players { playerId points } } }",
+            json!({ "sessionId": session_id }),
+            None,
+        )
+        .await.unwrap();
+    assert_eq!(
+        data["gameReport"]["players"],
+        json!([{ "playerId": "alice", "points": 1 }])
+    );
+}
+
+#[tokio::test]
+async fn late_answers_are_rejected() {
+    let quiz = harness::quiz();
+    next_question(&quiz).await;
+    next_question(&quiz).await;
+
+    // Choice "1" is the right one for the first question, not for the open one
+    let variables = json!({ "quizId": QUIZ_ID, "questionId": "0", "choiceId": "1" });
+    assert_eq!(
+        quiz.execute(ANSWER, variables, Some("alice"))
+            .await
+            .unwrap_err(),
+        "cannot answer"
+    );
+    assert!(answer(&quiz, "alice", "1", "0").await);
+}
+
+const GO_TO_QUESTION: &str = "mutation($quizId: ID!, $index: Int!) {
+    goToQuestion(quizId: $quizId, index: $index) { id }
+}";
+
+#[tokio::test]
+async fn questions_asked_again_start_over() {
+    let quiz = harness::quiz();
+    next_question(&quiz).await;
+    assert!(answer(&quiz, "alice", "0", "1").await);
+    next_question(&quiz).await;
+
+    let data = quiz
+        .execute(
+            GO_TO_QUESTION,
+            json!({ "quizId": QUIZ_ID, "index": 0 }),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(data["goToQuestion"]["id"], "0");
+    // The first answer doesn't count anymore, alice answers again
+    let data = quiz
+        .execute(
+            "query($id: ID!) { leaderboardForQuiz(id: $id) { list { points } } }",
+            json!({ "id": QUIZ_ID }),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(data["leaderboardForQuiz"]["list"], json!([{ "points": 0 }]));
+    assert!(answer(&quiz, "alice", "0", "1").await);
+
+    let variables = json!({ "quizId": QUIZ_ID, "index": 2 });
+    assert_eq!(
+        quiz.execute(GO_TO_QUESTION, variables, None)
+            .await
+            .unwrap_err(),
+        "question not found"
+    );
+    // The game goes on
+    assert_eq!(next_question(&quiz).await, "1");
+}
+
+#[tokio::test]
+async fn join_codes_expire_with_their_game() {
+    let quiz = harness::quiz();
+    let player = harness::player(&quiz);
+    next_question(&quiz).await;
+    let code = join_code(&quiz).await;
+    create_player(&player, json!({ "userName": "alice", "code": code })).await;
+
+    next_question(&quiz).await;
+    assert_eq!(next_question(&quiz).await, Value::Null);
+    let variables = json!({ "userName": "bob", "code": code });
+    assert_eq!(
+        player
+            .execute(CREATE_PLAYER, variables, None)
+            .await
+            .unwrap_err(),
+        "no game in progress for this code"
+    );
+}
+
+const LEADERBOARD_PAGE: &str = "query($id: ID!, $first: Int, $after: ID) {
+    leaderboardForQuiz(id: $id, first: $first, after: $after) {
+        list { id }
+        pageInfo { endCursor hasNextPage }
+        top(n: 1) { id }
+        myRank(neighbours: 1) { rank above { id } below { id } }
+    }
+}";
+
+#[tokio::test]
+async fn leaderboards_are_paged() {
+    let quiz = harness::quiz();
+    next_question(&quiz).await;
+    for (player_id, choice_id) in [("alice", "1"), ("bob", "1"), ("carol", "0")] {
+        answer(&quiz, player_id, "0", choice_id).await;
+    }
+
+    let variables = json!({ "id": QUIZ_ID, "first": 2 });
+    let data = quiz
+        .execute(LEADERBOARD_PAGE, variables, Some("bob"))
+        .await
+        .unwrap();
+    assert_eq!(
+        data["leaderboardForQuiz"],
+        json!({
+            "list": [{ "id": "alice" }, { "id": "bob" }],
+            "pageInfo": { "endCursor": "bob", "hasNextPage": true },
+            "top": [{ "id": "alice" }],
+            "myRank": { "rank": 1, "above": [{ "id": "alice" }], "below": [{ "id": "carol" }] },
+        })
+    );
+
+    let variables = json!({ "id": QUIZ_ID, "first": 2, "after": "bob" });
+    let data = quiz
+        .execute(LEADERBOARD_PAGE, variables, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        data["leaderboardForQuiz"]["list"],
+        json!([{ "id": "carol" }])
+    );
+    assert_eq!(
+        data["leaderboardForQuiz"]["pageInfo"],
+        json!({ "endCursor": "carol", "hasNextPage": false })
+    );
+
+    let variables = json!({ "id": QUIZ_ID, "first": 2, "after": "dave" });
+    assert_eq!(
+        quiz.execute(LEADERBOARD_PAGE, variables, None)
+            .await
+            .unwrap_err(),
+        "invalid cursor"
+    );
+}
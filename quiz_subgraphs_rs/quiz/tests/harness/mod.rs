@@ -0,0 +1,26 @@
+//! The quiz and player subgraphs served in-process, driven like the host and the players do
+//! with the GraphQL client of the workshop checker
+
+use std::net::SocketAddr;
+
+use axum::{Router, Server};
+use workshop_checker::Subgraph;
+
+/// The quiz subgraph with the quizzes of `data.json`, until the end of the test
+pub fn quiz() -> Subgraph {
+    serve(quiz::app(None).unwrap())
+}
+
+/// The player subgraph, checking the join codes against `quiz`
+pub fn player(quiz: &Subgraph) -> Subgraph {
+    serve(player::app_with_quiz_url(quiz.url()))
+}
+
+// On a free port of localhost
+fn serve(app: Router) -> Subgraph {
+    let server =
+        Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(app.into_make_service());
+    let addr = server.local_addr();
+    tokio::spawn(server);
+    Subgraph::new(addr)
+}
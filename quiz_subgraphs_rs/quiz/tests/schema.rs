@@ -0,0 +1,121 @@
+//! The router composes the supergraph from the schema files in `apollo_router`, they must be
+//! the SDL of this subgraph. Likewise `data.schema.json` must describe the quiz files read
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use async_graphql::parser::parse_schema;
+use async_graphql::parser::types::{TypeKind, TypeSystemDefinition};
+
+const SCHEMA_FILE: &str = "../../apollo_router/quiz_schema.graphql";
+const JSON_SCHEMA_FILE: &str = "data.schema.json";
+const SUPERGRAPH_FILE: &str = "../../apollo_router/supergraph.graphql";
+
+#[test]
+fn schema_file_is_up_to_date() {
+    check_up_to_date(SCHEMA_FILE, &quiz::schema_sdl());
+}
+
+// `data.json` and the quiz files of the hosts point their editor to it
+#[test]
+fn json_schema_file_is_up_to_date() {
+    // Printed with a line break by `cargo run -- json-schema > data.schema.json`
+    check_up_to_date(
+        JSON_SCHEMA_FILE,
+        &format!("{}\n", quiz::quiz_file_json_schema()),
+    );
+}
+
+// Overwrite `file` with `generated` when `UPDATE_SCHEMA` is set, or else fail unless they're the
+// same
+fn check_up_to_date(file: &str, generated: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(file);
+    if std::env::var_os("UPDATE_SCHEMA").is_some() {
+        std::fs::write(&path, generated).unwrap();
+        return;
+    }
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    if let Some((line, (expected, found))) = generated
+        .lines()
+        .zip(content.lines())
+        .enumerate()
+        .find(|(_, (expected, found))| expected != found)
+    {
+        panic!(
+            "{file} is out of date at line {}, `{found}` instead of `{expected}`, \
+             update it with `UPDATE_SCHEMA=1 cargo test --test schema`",
+            line + 1
+        );
+    }
+    assert!(
+        generated == content,
+        "{file} is out of date, update it with `UPDATE_SCHEMA=1 cargo test --test schema`"
+    );
+}
+
+// `Type.field` for the fields, enum values, union members and input fields of a schema, the
+// types of the composition itself left out
+fn members(sdl: &str) -> BTreeSet<String> {
+    let document = parse_schema(sdl).unwrap();
+    let mut members = BTreeSet::new();
+    for definition in document.definitions {
+        let TypeSystemDefinition::Type(definition) = definition else {
+            continue;
+        };
+        let definition = definition.node;
+        let type_name = definition.name.node.as_str();
+        if type_name.starts_with("join__") || type_name.starts_with("link__") {
+            continue;
+        }
+        let names: Vec<String> = match definition.kind {
+            TypeKind::Scalar => Vec::new(),
+            TypeKind::Object(object) => object
+                .fields
+                .into_iter()
+                .map(|field| field.node.name.node.to_string())
+                .collect(),
+            TypeKind::Interface(interface) => interface
+                .fields
+                .into_iter()
+                .map(|field| field.node.name.node.to_string())
+                .collect(),
+            TypeKind::Union(union) => union
+                .members
+                .into_iter()
+                .map(|member| member.node.to_string())
+                .collect(),
+            TypeKind::Enum(enum_type) => enum_type
+                .values
+                .into_iter()
+                .map(|value| value.node.value.node.to_string())
+                .collect(),
+            TypeKind::InputObject(input) => input
+                .fields
+                .into_iter()
+                .map(|field| field.node.name.node.to_string())
+                .collect(),
+        };
+        members.insert(type_name.to_string());
+        members.extend(names.into_iter().map(|name| format!("{type_name}.{name}")));
+    }
+    members
+}
+
+// Rover composes the supergraph and can't run here, but the supergraph must at least have what
+// the subgraphs have, and nothing else
+#[test]
+fn supergraph_file_is_up_to_date() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(SUPERGRAPH_FILE);
+    let supergraph = members(&std::fs::read_to_string(path).unwrap());
+    let mut subgraphs = members(&quiz::schema_sdl());
+    subgraphs.extend(members(&player::schema_sdl()));
+
+    let missing: Vec<_> = subgraphs.difference(&supergraph).collect();
+    let removed: Vec<_> = supergraph.difference(&subgraphs).collect();
+    assert!(
+        missing.is_empty() && removed.is_empty(),
+        "{SUPERGRAPH_FILE} is out of date, missing {missing:?} and no longer in the subgraphs \
+         {removed:?}, compose it again with `npm run compose` in apollo_router"
+    );
+}
@@ -1,32 +1,41 @@
 use std::collections::HashMap;
 
+#[allow(unused_imports)]
 use async_graphql::{Context, Object, SimpleObject, Subscription, ID};
-use futures_util::{Stream, StreamExt, stream};
+#[allow(unused_imports)]
+use futures_util::{stream, Stream, StreamExt};
 use tokio::sync::{
     broadcast::{self},
     RwLock,
 };
+#[allow(unused_imports)]
 use tokio_stream::wrappers::BroadcastStream;
 
 pub(crate) struct InMemoryDb {
+    #[allow(dead_code)]
     quizzes: RwLock<HashMap<ID, Quiz>>,
     // leaderboard by quiz id -> points by player_id
+    #[allow(dead_code)]
     leaderboard: RwLock<HashMap<ID, HashMap<ID, usize>>>,
 }
 
 impl InMemoryDb {
+    #[allow(dead_code, unused_variables)]
     pub(crate) async fn next_question(&self, quiz_id: &ID) -> Option<Question> {
         todo!()
     }
 
+    #[allow(dead_code, unused_variables)]
     pub(crate) async fn get_player_points(&self, player_id: &ID, quiz_id: &ID) -> Option<usize> {
         todo!()
     }
 
+    #[allow(dead_code, unused_variables)]
     pub(crate) async fn compute_leaderboard(&self, quiz_id: &ID) -> Option<Leaderboard> {
         todo!()
     }
 
+    #[allow(dead_code, unused_variables)]
     pub(crate) async fn answer(
         &self,
         player_id: &ID,
@@ -37,10 +46,12 @@ impl InMemoryDb {
         todo!()
     }
 
+    #[allow(dead_code, unused_variables)]
     pub(crate) async fn get_quiz(&self, quiz_id: &ID) -> Option<Quiz> {
         todo!()
     }
 
+    #[allow(dead_code)]
     pub(crate) async fn get_quizzes(&self) -> Vec<Quiz> {
         todo!()
     }
@@ -48,27 +59,34 @@ impl InMemoryDb {
 
 #[derive(Default)]
 pub(crate) struct InMemoryBroker {
+    #[allow(dead_code)]
     quizzes: RwLock<HashMap<ID, broadcast::Sender<Question>>>,
+    #[allow(dead_code)]
     leaderboard: RwLock<HashMap<ID, broadcast::Sender<Leaderboard>>>,
 }
 
 impl InMemoryBroker {
+    #[allow(dead_code, unused_variables)]
     pub(crate) async fn subscribe_quiz(&self, quiz_id: &ID) -> impl Stream<Item = Question> {
         stream::empty()
     }
 
+    #[allow(dead_code, unused_variables)]
     pub(crate) async fn next_question(&self, quiz_id: &ID, question: Question) {
         todo!()
     }
 
+    #[allow(dead_code, unused_variables)]
     pub(crate) async fn unsubscribe_quiz(&self, quiz_id: &ID) {
         todo!()
     }
 
+    #[allow(dead_code, unused_variables)]
     pub(crate) async fn unsubscribe_leaderboard(&self, quiz_id: &ID) {
         todo!()
     }
 
+    #[allow(dead_code, unused_variables)]
     pub(crate) async fn subscribe_leaderboard(
         &self,
         quiz_id: &ID,
@@ -76,6 +94,7 @@ impl InMemoryBroker {
         stream::empty()
     }
 
+    #[allow(dead_code, unused_variables)]
     pub(crate) async fn broadcast_leaderboard(&self, quiz_id: &ID, leaderboard: Leaderboard) {
         todo!()
     }
@@ -86,11 +105,13 @@ pub(crate) struct QueryRoot;
 #[Object]
 impl QueryRoot {
     // TODO
+    #[allow(unused_variables)]
     async fn test<'ctx>(&self, ctx: &Context<'ctx>, id: ID) -> String {
         String::from("test")
     }
 }
 
+#[allow(dead_code)]
 pub(crate) struct SubscriptionRoot;
 
 // #[Subscription]
@@ -105,6 +126,7 @@ pub(crate) struct SubscriptionRoot;
 // }
 // }
 
+#[allow(dead_code)]
 pub(crate) struct MutationRoot;
 
 // #[Object]
@@ -112,6 +134,7 @@ pub(crate) struct MutationRoot;
 //     // TODO
 // }
 
+#[allow(dead_code)]
 #[derive(Clone, SimpleObject, Debug)]
 pub(crate) struct Player {
     test: usize,
@@ -127,6 +150,7 @@ pub(crate) struct Question {
     test: usize,
 }
 
+#[allow(dead_code)]
 #[derive(Clone, Default, SimpleObject, Debug)]
 pub(crate) struct Choice {
     test: usize,
@@ -137,11 +161,13 @@ pub(crate) struct Leaderboard {
     test: usize,
 }
 
+#[allow(dead_code)]
 #[derive(Clone, Default, SimpleObject, Debug)]
 pub(crate) struct Response {
     test: usize,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for InMemoryDb {
     fn default() -> Self {
         Self {
@@ -1,32 +1,39 @@
 use std::collections::HashMap;
 
 use async_graphql::{Context, Object, SimpleObject, Subscription, ID};
+#[allow(unused_imports)]
 use futures_util::{stream, Stream, StreamExt};
 use tokio::sync::{
     broadcast::{self},
     RwLock,
 };
+#[allow(unused_imports)]
 use tokio_stream::wrappers::BroadcastStream;
 
 pub(crate) struct InMemoryDb {
     quizzes: RwLock<HashMap<ID, Quiz>>,
     // leaderboard by quiz id -> points by player_id
+    #[allow(dead_code)]
     leaderboard: RwLock<HashMap<ID, HashMap<ID, usize>>>,
 }
 
 impl InMemoryDb {
+    #[allow(unused_variables)]
     pub(crate) async fn next_question(&self, quiz_id: &ID) -> Option<Question> {
         Some(Question::default())
     }
 
+    #[allow(unused_variables)]
     pub(crate) async fn get_player_points(&self, player_id: &ID, quiz_id: &ID) -> Option<usize> {
         Some(2)
     }
 
+    #[allow(unused_variables)]
     pub(crate) async fn compute_leaderboard(&self, quiz_id: &ID) -> Option<Leaderboard> {
         Some(Leaderboard::default())
     }
 
+    #[allow(unused_variables)]
     pub(crate) async fn answer(
         &self,
         player_id: &ID,
@@ -48,11 +55,14 @@ impl InMemoryDb {
 
 #[derive(Default)]
 pub(crate) struct InMemoryBroker {
+    #[allow(dead_code)]
     quizzes: RwLock<HashMap<ID, broadcast::Sender<Question>>>,
+    #[allow(dead_code)]
     leaderboard: RwLock<HashMap<ID, broadcast::Sender<Leaderboard>>>,
 }
 
 impl InMemoryBroker {
+    #[allow(unused_variables)]
     pub(crate) async fn subscribe_quiz(&self, quiz_id: &ID) -> impl Stream<Item = Question> {
         stream::iter(vec![Question {
             id: "0".into(),
@@ -79,18 +89,22 @@ impl InMemoryBroker {
         }])
     }
 
+    #[allow(unused_variables)]
     pub(crate) async fn next_question(&self, quiz_id: &ID, question: Question) {
         todo!()
     }
 
+    #[allow(unused_variables)]
     pub(crate) async fn unsubscribe_quiz(&self, quiz_id: &ID) {
         todo!()
     }
 
+    #[allow(unused_variables)]
     pub(crate) async fn unsubscribe_leaderboard(&self, quiz_id: &ID) {
         todo!()
     }
 
+    #[allow(unused_variables)]
     pub(crate) async fn subscribe_leaderboard(
         &self,
         quiz_id: &ID,
@@ -98,6 +112,7 @@ impl InMemoryBroker {
         stream::iter(vec![Leaderboard::default()])
     }
 
+    #[allow(unused_variables)]
     pub(crate) async fn broadcast_leaderboard(&self, quiz_id: &ID, leaderboard: Leaderboard) {
         todo!()
     }
@@ -181,12 +196,7 @@ impl MutationRoot {
         question_id: ID,
         choice_id: Option<ID>,
     ) -> async_graphql::Result<Response> {
-        let player_id: &ID = match ctx.data_opt() {
-            Some(id) => id,
-            None => {
-                return Err("cannot find the player header".into());
-            }
-        };
+        let player_id = subgraph_core::player_id(ctx)?;
 
         let in_memory_db: &InMemoryDb = ctx.data_unchecked();
 
@@ -238,6 +248,7 @@ pub(crate) struct Quiz {
     pub(crate) id: ID,
     pub(crate) title: String,
     pub(crate) questions: Vec<Question>,
+    #[allow(dead_code)]
     #[graphql(skip)]
     pub(crate) current_question: i8,
 }
@@ -247,6 +258,7 @@ pub(crate) struct Question {
     pub(crate) id: ID,
     pub(crate) title: String,
     pub(crate) choices: Vec<Choice>,
+    #[allow(dead_code)]
     #[graphql(skip)]
     pub(crate) good_answer: ID,
 }
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+use async_graphql::ID;
+use futures_util::stream::BoxStream;
+use futures_util::StreamExt;
+use tokio::sync::{broadcast, RwLock};
+use tokio_stream::wrappers::BroadcastStream;
+
+// Enough for the messages of a game sent before a subscriber catches up
+const DEFAULT_CAPACITY: usize = 16;
+
+/// A broadcast channel per id, like the questions of each quiz, created by its first subscriber
+pub struct Channels<T> {
+    senders: RwLock<HashMap<ID, broadcast::Sender<T>>>,
+    capacity: usize,
+}
+
+impl<T: Clone + Send + 'static> Channels<T> {
+    /// Channels keeping up to `capacity` messages for subscribers lagging behind
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            senders: Default::default(),
+            capacity,
+        }
+    }
+
+    /// Receiver of the channel of `id`, for subscribers that handle the lagged messages
+    pub async fn receiver(&self, id: &ID) -> broadcast::Receiver<T> {
+        if let Some(sender) = self.senders.read().await.get(id) {
+            return sender.subscribe();
+        }
+        self.senders
+            .write()
+            .await
+            .entry(id.clone())
+            .or_insert_with(|| broadcast::channel(self.capacity).0)
+            .subscribe()
+    }
+
+    /// Messages sent to the channel of `id` from now on, skipping the ones lost when lagging
+    /// behind. The stream ends when the channel is closed
+    pub async fn subscribe(&self, id: &ID) -> BoxStream<'static, T> {
+        BroadcastStream::new(self.receiver(id).await)
+            .filter_map(|message| async move { message.ok() })
+            .boxed()
+    }
+
+    /// Send `message` to the subscribers of `id`, if any
+    pub async fn send(&self, id: &ID, message: T) {
+        if let Some(sender) = self.senders.read().await.get(id) {
+            let _err = sender.send(message);
+        }
+    }
+
+    /// Drop the channel of `id`, ending the streams of its subscribers
+    pub async fn close(&self, id: &ID) {
+        self.senders.write().await.remove(id);
+    }
+}
+
+impl<T: Clone + Send + 'static> Default for Channels<T> {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
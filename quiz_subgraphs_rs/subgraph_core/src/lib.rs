@@ -0,0 +1,53 @@
+//! What every subgraph of the workshop shares: the server bootstrap and its configuration, the
+//! GraphQL routes, the player sent by the clients and the broadcast channels of subscriptions
+
+mod broker;
+mod player;
+mod router;
+mod server;
+
+use async_graphql::{ObjectType, SDLExportOptions, Schema, SchemaBuilder, SubscriptionType};
+
+pub use broker::Channels;
+pub use player::{player_data, player_id, player_id_from_headers};
+pub use router::{graphiql, RouterBuilder};
+pub use server::{run, serve, ServerArgs};
+
+const FEDERATION_V2_3: &str = "https://specs.apollo.dev/federation/v2.3";
+const FEDERATION_V2_4: &str = "https://specs.apollo.dev/federation/v2.4";
+
+/// Schema of a subgraph, federated with its subscriptions.
+///
+/// The root types must be named `Query`, `Mutation` and `Subscription` like in the supergraph,
+/// whatever the name of their Rust types, as the federated SDL has no schema definition.
+pub fn schema_builder<Query, Mutation, Subscription>(
+    query: Query,
+    mutation: Mutation,
+    subscription: Subscription,
+) -> SchemaBuilder<Query, Mutation, Subscription>
+where
+    Query: ObjectType + 'static,
+    Mutation: ObjectType + 'static,
+    Subscription: SubscriptionType + 'static,
+{
+    Schema::build(query, mutation, subscription)
+        .enable_federation()
+        .enable_subscription_in_federation()
+        .limit_complexity(100)
+}
+
+/// SDL of a subgraph with its federation directives, as composed by the router
+pub fn schema_sdl<Query, Mutation, Subscription>(
+    schema: &Schema<Query, Mutation, Subscription>,
+) -> String
+where
+    Query: ObjectType + 'static,
+    Mutation: ObjectType + 'static,
+    Subscription: SubscriptionType + 'static,
+{
+    // async-graphql links federation v2.3, the router composes with federation 2.4 (see
+    // `rover.yaml`) as its subscriptions need it
+    schema
+        .sdl_with_options(SDLExportOptions::new().federation())
+        .replace(FEDERATION_V2_3, FEDERATION_V2_4)
+}
@@ -0,0 +1,30 @@
+use async_graphql::{Context, Data, ID};
+use http::HeaderMap;
+use serde_json::Value;
+
+/// Player of a request, from its `player` header
+pub fn player_id_from_headers(headers: &HeaderMap) -> Option<ID> {
+    headers
+        .get("player")
+        .and_then(|value| value.to_str().map(ID::from).ok())
+}
+
+/// Data of a request with its player, from the `player` field of the `connection_init` payload
+/// for subscriptions or from the `player` header
+pub fn player_data(headers: &HeaderMap, payload: &Value) -> Data {
+    let mut data = Data::default();
+    if let Some(player_id) = payload["player"]
+        .as_str()
+        .map(ID::from)
+        .or_else(|| player_id_from_headers(headers))
+    {
+        data.insert(player_id);
+    }
+    data
+}
+
+/// Player of the request, an error for resolvers that need one when it has none
+pub fn player_id<'ctx>(ctx: &Context<'ctx>) -> async_graphql::Result<&'ctx ID> {
+    ctx.data_opt()
+        .ok_or_else(|| async_graphql::Error::new("cannot find the player header"))
+}
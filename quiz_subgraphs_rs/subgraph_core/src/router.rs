@@ -0,0 +1,116 @@
+use async_graphql::http::{GraphiQLSource, ALL_WEBSOCKET_PROTOCOLS};
+use async_graphql::{Data, ObjectType, Schema, SubscriptionType};
+use async_graphql_axum::{GraphQLProtocol, GraphQLRequest, GraphQLResponse, GraphQLWebSocket};
+use axum::extract::WebSocketUpgrade;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, MethodRouter};
+use axum::{Extension, Router};
+use http::HeaderMap;
+use serde_json::Value;
+use tower_http::cors::CorsLayer;
+
+use crate::player::player_data;
+
+/// Data of a request from its headers and, for subscriptions, the `connection_init` payload
+type RequestData = fn(&HeaderMap, &Value) -> Data;
+
+/// Router of a subgraph: GraphiQL and queries on `/`, subscriptions on `/ws`, open to every
+/// origin
+pub struct RouterBuilder<Query, Mutation, Subscription> {
+    schema: Schema<Query, Mutation, Subscription>,
+    request_data: RequestData,
+    routes: Router,
+}
+
+impl<Query, Mutation, Subscription> RouterBuilder<Query, Mutation, Subscription>
+where
+    Query: ObjectType + 'static,
+    Mutation: ObjectType + 'static,
+    Subscription: SubscriptionType + 'static,
+{
+    /// Requests get their player with [`player_data`] unless told otherwise
+    pub fn new(schema: Schema<Query, Mutation, Subscription>) -> Self {
+        Self {
+            schema,
+            request_data: player_data,
+            routes: Router::new(),
+        }
+    }
+
+    /// Get the data of the requests with `request_data` instead of [`player_data`]
+    pub fn request_data(mut self, request_data: RequestData) -> Self {
+        self.request_data = request_data;
+        self
+    }
+
+    /// Serve another route next to the GraphQL ones
+    pub fn route(mut self, path: &str, method_router: MethodRouter) -> Self {
+        self.routes = self.routes.route(path, method_router);
+        self
+    }
+
+    pub fn finish(self) -> Router {
+        self.routes
+            .route(
+                "/",
+                get(graphiql).post(graphql_handler::<Query, Mutation, Subscription>),
+            )
+            .route(
+                "/ws",
+                get(graphql_ws_handler::<Query, Mutation, Subscription>),
+            )
+            .layer(CorsLayer::permissive())
+            .layer(Extension(self.schema))
+            .layer(Extension(self.request_data))
+    }
+}
+
+async fn graphql_handler<Query, Mutation, Subscription>(
+    Extension(schema): Extension<Schema<Query, Mutation, Subscription>>,
+    Extension(request_data): Extension<RequestData>,
+    headers: HeaderMap,
+    req: GraphQLRequest,
+) -> GraphQLResponse
+where
+    Query: ObjectType + 'static,
+    Mutation: ObjectType + 'static,
+    Subscription: SubscriptionType + 'static,
+{
+    let mut req = req.into_inner();
+    req.data = request_data(&headers, &Value::Null);
+    schema.execute(req).await.into()
+}
+
+// The upgrade request has headers too, browsers can't set them though
+async fn graphql_ws_handler<Query, Mutation, Subscription>(
+    Extension(schema): Extension<Schema<Query, Mutation, Subscription>>,
+    Extension(request_data): Extension<RequestData>,
+    protocol: GraphQLProtocol,
+    headers: HeaderMap,
+    websocket: WebSocketUpgrade,
+) -> Response
+where
+    Query: ObjectType + 'static,
+    Mutation: ObjectType + 'static,
+    Subscription: SubscriptionType + 'static,
+{
+    websocket
+        .protocols(ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |stream| {
+            GraphQLWebSocket::new(stream, schema, protocol)
+                .on_connection_init(
+                    move |payload| async move { Ok(request_data(&headers, &payload)) },
+                )
+                .serve()
+        })
+}
+
+/// GraphiQL, sending queries to `/` and subscriptions to `/ws`
+pub async fn graphiql() -> impl IntoResponse {
+    axum::response::Html(
+        GraphiQLSource::build()
+            .endpoint("/")
+            .subscription_endpoint("/ws")
+            .finish(),
+    )
+}
@@ -0,0 +1,37 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use axum::{Router, Server};
+use clap::Parser;
+
+/// Where a subgraph listens, to flatten in the command line of the subgraphs that have commands
+#[derive(Parser)]
+pub struct ServerArgs {
+    /// Port to listen on, 4005 for the quiz subgraph and 4006 for the player one by default
+    #[arg(long, env = "PORT")]
+    port: Option<u16>,
+    #[arg(long, default_value_t = IpAddr::V4(Ipv4Addr::UNSPECIFIED))]
+    bind: IpAddr,
+}
+
+impl ServerArgs {
+    pub fn addr(&self, default_port: u16) -> SocketAddr {
+        (self.bind, self.port.unwrap_or(default_port)).into()
+    }
+}
+
+/// Serve `app` where the command line (or the `PORT` env) tells to, on `default_port` otherwise
+pub async fn run(app: Router, default_port: u16) {
+    serve(app, ServerArgs::parse().addr(default_port)).await;
+}
+
+pub async fn serve(app: Router, addr: SocketAddr) {
+    println!(
+        "Explore this graph at https://studio.apollographql.com/sandbox/explorer?endpoint={}",
+        urlencoding::encode(&format!("http://localhost:{}", addr.port()))
+    );
+
+    Server::bind(&addr)
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
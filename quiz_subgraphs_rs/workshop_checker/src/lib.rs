@@ -0,0 +1,82 @@
+//! Checks the exercises of the workshop against the binary of a step crate, like `quiz_1` or
+//! `player_0`, and reports which ones are done
+
+mod player;
+mod quiz;
+mod subgraph;
+
+use futures_util::future::BoxFuture;
+
+use subgraph::Process;
+pub use subgraph::{Subgraph, Subscription};
+
+/// An exercise, checked against a subgraph started just for it
+struct Exercise {
+    name: &'static str,
+    /// What to implement for the check to pass
+    todo: &'static str,
+    check: fn(Subgraph) -> BoxFuture<'static, Result<(), String>>,
+}
+
+/// Check the exercises of the quiz subgraph against its `binary`, panic unless they all pass
+pub fn check_quiz(binary: &str) {
+    check("quiz", binary, quiz::exercises());
+}
+
+/// Check the exercises of the player subgraph against its `binary`, panic unless they all pass
+pub fn check_player(binary: &str) {
+    check("player", binary, player::exercises());
+}
+
+fn check(subgraph_name: &str, binary: &str, exercises: Vec<Exercise>) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut done = 0;
+    println!("Exercises of the {subgraph_name} subgraph:");
+    for exercise in &exercises {
+        match runtime.block_on(run(binary, exercise)) {
+            Ok(()) => {
+                done += 1;
+                println!("  ✓ {}", exercise.name);
+            }
+            Err(err) => {
+                println!("  ✗ {}", exercise.name);
+                println!("      to do: {}", exercise.todo);
+                for line in err.lines() {
+                    println!("      {line}");
+                }
+            }
+        }
+    }
+    println!("{done}/{} exercises done", exercises.len());
+
+    assert!(
+        done == exercises.len(),
+        "{} exercises of the {subgraph_name} subgraph to do",
+        exercises.len() - done
+    );
+}
+
+// A new process for each exercise, so the state left by an exercise doesn't fail the next ones
+async fn run(binary: &str, exercise: &Exercise) -> Result<(), String> {
+    let process = Process::start(binary).await?;
+    let result = (exercise.check)(process.subgraph()).await;
+    let panic = process.stop();
+    match (result, panic) {
+        (Ok(()), _) => Ok(()),
+        (Err(err), Some(panic)) => Err(format!("{err}\n{panic}")),
+        (Err(err), None) => Err(err),
+    }
+}
+
+/// Error unless `found` is `expected`
+fn expect_eq(
+    what: &str,
+    found: &serde_json::Value,
+    expected: serde_json::Value,
+) -> Result<(), String> {
+    if *found == expected {
+        Ok(())
+    } else {
+        Err(format!("{what}: expected {expected}, got {found}"))
+    }
+}
@@ -0,0 +1,169 @@
+use serde_json::{json, Value};
+
+use crate::subgraph::Subgraph;
+use crate::{expect_eq, Exercise};
+
+const QUIZ_ID: &str = "0";
+
+pub(crate) fn exercises() -> Vec<Exercise> {
+    vec![
+        Exercise {
+            name: "createPlayer mutation",
+            todo: "the Player type, MutationRoot::create_player and InMemoryDb::create_player",
+            check: |player| Box::pin(create_player(player)),
+        },
+        Exercise {
+            name: "player query",
+            todo: "QueryRoot::player and InMemoryDb::get_player",
+            check: |player| Box::pin(player_query(player)),
+        },
+        Exercise {
+            name: "playersForAQuiz query",
+            todo: "QueryRoot::players_for_a_quiz and InMemoryDb::players_for_quiz",
+            check: |player| Box::pin(players_for_a_quiz(player)),
+        },
+        Exercise {
+            name: "Player entity",
+            todo: "QueryRoot::find_player_by_id_and_quiz_id with #[graphql(entity)] and InMemoryDb::get_player",
+            check: |player| Box::pin(player_entity(player)),
+        },
+        Exercise {
+            name: "playersForAQuiz subscription",
+            todo: "SubscriptionRoot::players_for_a_quiz, InMemoryBroker::subscribe_new_players and new_players",
+            check: |player| Box::pin(players_subscription(player)),
+        },
+    ]
+}
+
+// The player created, with its `id`, `name` and `quizId`
+async fn new_player(player: &Subgraph, name: &str, quiz_id: &str) -> Result<Value, String> {
+    let data = player
+        .execute(
+            &format!(
+                r#"mutation {{ createPlayer(userName: "{name}", quizId: "{quiz_id}") {{ id name quizId }} }}"#
+            ),
+            json!({}),
+            None,
+        )
+        .await?;
+    Ok(data["createPlayer"].clone())
+}
+
+// Names of the players in a list, sorted as the order of the players doesn't matter
+fn names(players: &Value) -> Value {
+    let mut names: Vec<&str> = players
+        .as_array()
+        .map(|players| {
+            players
+                .iter()
+                .filter_map(|player| player["name"].as_str())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    json!(names)
+}
+
+async fn create_player(player: Subgraph) -> Result<(), String> {
+    let alice = new_player(&player, "alice", QUIZ_ID).await?;
+    if alice["id"].as_str().unwrap_or_default().is_empty() {
+        return Err(format!("the player has no id: {alice}"));
+    }
+    expect_eq("name of the player", &alice["name"], json!("alice"))?;
+    expect_eq("quiz of the player", &alice["quizId"], json!(QUIZ_ID))?;
+
+    let bob = new_player(&player, "bob", QUIZ_ID).await?;
+    if bob["id"] == alice["id"] {
+        return Err(format!("two players have the same id {}", bob["id"]));
+    }
+    match new_player(&player, "alice", QUIZ_ID).await {
+        Ok(_) => Err("creating a player with a name already taken should be an error".to_string()),
+        Err(_) => Ok(()),
+    }
+}
+
+async fn player_query(player: Subgraph) -> Result<(), String> {
+    let alice = new_player(&player, "alice", QUIZ_ID).await?;
+    let data = player
+        .execute(
+            &format!(
+                r#"{{ player(playerId: "{}") {{ id name quizId }} }}"#,
+                alice["id"].as_str().unwrap_or_default()
+            ),
+            json!({}),
+            None,
+        )
+        .await?;
+    expect_eq("player found by id", &data["player"], alice)?;
+    match player
+        .execute(r#"{ player(playerId: "unknown") { id } }"#, json!({}), None)
+        .await
+    {
+        Ok(data) => Err(format!(
+            "an unknown player should be an error, got {}",
+            data["player"]
+        )),
+        Err(_) => Ok(()),
+    }
+}
+
+async fn players_for_a_quiz(player: Subgraph) -> Result<(), String> {
+    new_player(&player, "alice", QUIZ_ID).await?;
+    new_player(&player, "bob", QUIZ_ID).await?;
+    new_player(&player, "carol", "another quiz").await?;
+    let data = player
+        .execute(
+            &format!(r#"{{ playersForAQuiz(quizId: "{QUIZ_ID}") {{ name }} }}"#),
+            json!({}),
+            None,
+        )
+        .await?;
+    expect_eq(
+        "players of the quiz",
+        &names(&data["playersForAQuiz"]),
+        json!(["alice", "bob"]),
+    )
+}
+
+async fn player_entity(player: Subgraph) -> Result<(), String> {
+    let alice = new_player(&player, "alice", QUIZ_ID).await?;
+    let data = player
+        .execute(
+            &format!(
+                r#"{{
+                    _entities(representations: [{{ __typename: "Player", id: "{}", quizId: "{QUIZ_ID}" }}]) {{
+                        ... on Player {{ id name quizId }}
+                    }}
+                }}"#,
+                alice["id"].as_str().unwrap_or_default()
+            ),
+            json!({}),
+            None,
+        )
+        .await?;
+    expect_eq("player entity", &data["_entities"], json!([alice]))
+}
+
+async fn players_subscription(player: Subgraph) -> Result<(), String> {
+    let mut players = player
+        .subscribe(
+            &format!(r#"subscription {{ playersForAQuiz(quizId: "{QUIZ_ID}") {{ name }} }}"#),
+            json!({}),
+            None,
+        )
+        .await?;
+    new_player(&player, "alice", QUIZ_ID).await?;
+    let event = players.next().await?.unwrap_or_default();
+    expect_eq(
+        "players sent to the subscription when alice joins",
+        &names(&event["playersForAQuiz"]),
+        json!(["alice"]),
+    )?;
+    new_player(&player, "bob", QUIZ_ID).await?;
+    let event = players.next().await?.unwrap_or_default();
+    expect_eq(
+        "players sent to the subscription when bob joins",
+        &names(&event["playersForAQuiz"]),
+        json!(["alice", "bob"]),
+    )
+}
@@ -0,0 +1,292 @@
+use serde_json::{json, Value};
+
+use crate::subgraph::Subgraph;
+use crate::{expect_eq, Exercise};
+
+// The quiz of the workshop, with two questions
+const QUIZ_ID: &str = "0";
+const GOOD_ANSWERS: [&str; 2] = ["1", "0"];
+
+pub(crate) fn exercises() -> Vec<Exercise> {
+    vec![
+        Exercise {
+            name: "allQuizzes query",
+            todo: "the Quiz, Question and Choice types, QueryRoot::all_quizzes and InMemoryDb::get_quizzes",
+            check: |quiz| Box::pin(all_quizzes(quiz)),
+        },
+        Exercise {
+            name: "Quiz entity",
+            todo: "QueryRoot::find_quiz_by_id with #[graphql(entity)] and InMemoryDb::get_quiz",
+            check: |quiz| Box::pin(quiz_entity(quiz)),
+        },
+        Exercise {
+            name: "nextQuestion mutation",
+            todo: "MutationRoot::next_question and InMemoryDb::next_question",
+            check: |quiz| Box::pin(next_question(quiz)),
+        },
+        Exercise {
+            name: "answer mutation",
+            todo: "MutationRoot::answer and InMemoryDb::answer",
+            check: |quiz| Box::pin(answer(quiz)),
+        },
+        Exercise {
+            name: "leaderboardForQuiz query",
+            todo: "QueryRoot::leaderboard_for_quiz and InMemoryDb::compute_leaderboard",
+            check: |quiz| Box::pin(leaderboard(quiz)),
+        },
+        Exercise {
+            name: "Player entity",
+            todo: "QueryRoot::find_player_by_id_and_quiz_id with #[graphql(entity)] and InMemoryDb::get_player_points",
+            check: |quiz| Box::pin(player_entity(quiz)),
+        },
+        Exercise {
+            name: "newQuestion subscription",
+            todo: "SubscriptionRoot::new_question, InMemoryBroker::subscribe_quiz, next_question and unsubscribe_quiz",
+            check: |quiz| Box::pin(new_question_subscription(quiz)),
+        },
+        Exercise {
+            name: "leaderboardForQuiz subscription",
+            todo: "SubscriptionRoot::leaderboard_for_quiz, InMemoryBroker::subscribe_leaderboard, broadcast_leaderboard and unsubscribe_leaderboard",
+            check: |quiz| Box::pin(leaderboard_subscription(quiz)),
+        },
+    ]
+}
+
+// Id of the question opened, `null` once the quiz is over
+async fn open_next_question(quiz: &Subgraph) -> Result<Value, String> {
+    let data = quiz
+        .execute(
+            &format!(r#"mutation {{ nextQuestion(quizId: "{QUIZ_ID}") {{ id }} }}"#),
+            json!({}),
+            None,
+        )
+        .await?;
+    Ok(data["nextQuestion"]["id"].clone())
+}
+
+async fn send_answer(
+    quiz: &Subgraph,
+    player: &str,
+    question: &str,
+    choice: &str,
+) -> Result<Value, String> {
+    let data = quiz
+        .execute(
+            &format!(
+                r#"mutation {{
+                    answer(quizId: "{QUIZ_ID}", questionId: "{question}", choiceId: "{choice}") {{
+                        success
+                        rightChoice {{ id }}
+                    }}
+                }}"#
+            ),
+            json!({}),
+            Some(player),
+        )
+        .await?;
+    Ok(data["answer"].clone())
+}
+
+async fn all_quizzes(quiz: Subgraph) -> Result<(), String> {
+    let data = quiz
+        .execute(
+            "{ allQuizzes { id title questions { id title choices { id text } } } }",
+            json!({}),
+            None,
+        )
+        .await?;
+    let quizzes = data["allQuizzes"].as_array().cloned().unwrap_or_default();
+    let workshop_quiz = quizzes
+        .iter()
+        .find(|quiz| quiz["id"] == QUIZ_ID)
+        .ok_or_else(|| format!("no quiz with the id {QUIZ_ID:?} in {}", data["allQuizzes"]))?;
+    if workshop_quiz["title"]
+        .as_str()
+        .unwrap_or_default()
+        .is_empty()
+    {
+        return Err("the quiz has no title".to_string());
+    }
+    let questions = workshop_quiz["questions"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    expect_eq("questions of the quiz", &json!(questions.len()), json!(2))?;
+    for question in &questions {
+        expect_eq(
+            "choices of each question",
+            &json!(question["choices"].as_array().map_or(0, Vec::len)),
+            json!(4),
+        )?;
+    }
+    Ok(())
+}
+
+async fn quiz_entity(quiz: Subgraph) -> Result<(), String> {
+    let data = quiz
+        .execute(
+            &format!(
+                r#"{{
+                    _entities(representations: [{{ __typename: "Quiz", id: "{QUIZ_ID}" }}]) {{
+                        ... on Quiz {{ id questions {{ id }} }}
+                    }}
+                }}"#
+            ),
+            json!({}),
+            None,
+        )
+        .await?;
+    expect_eq(
+        "quiz entity",
+        &data["_entities"],
+        json!([{ "id": QUIZ_ID, "questions": [{ "id": "0" }, { "id": "1" }] }]),
+    )
+}
+
+async fn next_question(quiz: Subgraph) -> Result<(), String> {
+    expect_eq(
+        "first question",
+        &open_next_question(&quiz).await?,
+        json!("0"),
+    )?;
+    expect_eq(
+        "second question",
+        &open_next_question(&quiz).await?,
+        json!("1"),
+    )?;
+    expect_eq(
+        "next question after the last one",
+        &open_next_question(&quiz).await?,
+        Value::Null,
+    )?;
+    // The quiz can be played again
+    expect_eq(
+        "first question of the next game",
+        &open_next_question(&quiz).await?,
+        json!("0"),
+    )
+}
+
+async fn answer(quiz: Subgraph) -> Result<(), String> {
+    open_next_question(&quiz).await?;
+    let good_answer = GOOD_ANSWERS[0];
+    expect_eq(
+        "good answer",
+        &send_answer(&quiz, "1", "0", good_answer).await?,
+        json!({ "success": true, "rightChoice": { "id": good_answer } }),
+    )?;
+    expect_eq(
+        "wrong answer",
+        &send_answer(&quiz, "2", "0", "3").await?,
+        json!({ "success": false, "rightChoice": { "id": good_answer } }),
+    )?;
+    match quiz
+        .execute(
+            &format!(
+                r#"mutation {{ answer(quizId: "{QUIZ_ID}", questionId: "0", choiceId: "1") {{ success }} }}"#
+            ),
+            json!({}),
+            None,
+        )
+        .await
+    {
+        Ok(_) => Err("an answer without the player header should be an error".to_string()),
+        Err(_) => Ok(()),
+    }
+}
+
+async fn leaderboard(quiz: Subgraph) -> Result<(), String> {
+    open_next_question(&quiz).await?;
+    send_answer(&quiz, "1", "0", "3").await?;
+    send_answer(&quiz, "2", "0", GOOD_ANSWERS[0]).await?;
+    open_next_question(&quiz).await?;
+    send_answer(&quiz, "1", "1", GOOD_ANSWERS[1]).await?;
+    send_answer(&quiz, "2", "1", GOOD_ANSWERS[1]).await?;
+
+    let data = quiz
+        .execute(
+            &format!(r#"{{ leaderboardForQuiz(id: "{QUIZ_ID}") {{ quiz {{ id }} list {{ id points }} }} }}"#),
+            json!({}),
+            None,
+        )
+        .await?;
+    expect_eq(
+        "leaderboard, the best players first",
+        &data["leaderboardForQuiz"],
+        json!({
+            "quiz": { "id": QUIZ_ID },
+            "list": [{ "id": "2", "points": 2 }, { "id": "1", "points": 1 }],
+        }),
+    )
+}
+
+async fn player_entity(quiz: Subgraph) -> Result<(), String> {
+    open_next_question(&quiz).await?;
+    send_answer(&quiz, "1", "0", GOOD_ANSWERS[0]).await?;
+
+    let data = quiz
+        .execute(
+            &format!(
+                r#"{{
+                    _entities(representations: [{{ __typename: "Player", id: "1", quizId: "{QUIZ_ID}" }}]) {{
+                        ... on Player {{ id points }}
+                    }}
+                }}"#
+            ),
+            json!({}),
+            None,
+        )
+        .await?;
+    expect_eq(
+        "player entity",
+        &data["_entities"],
+        json!([{ "id": "1", "points": 1 }]),
+    )
+}
+
+async fn new_question_subscription(quiz: Subgraph) -> Result<(), String> {
+    let mut questions = quiz
+        .subscribe(
+            &format!(r#"subscription {{ newQuestion(quizId: "{QUIZ_ID}") {{ id }} }}"#),
+            json!({}),
+            None,
+        )
+        .await?;
+    for question_id in ["0", "1"] {
+        open_next_question(&quiz).await?;
+        expect_eq(
+            "question sent to the subscription",
+            &questions.next().await?.unwrap_or_default(),
+            json!({ "newQuestion": { "id": question_id } }),
+        )?;
+    }
+    open_next_question(&quiz).await?;
+    match questions.next().await? {
+        None => Ok(()),
+        Some(event) => Err(format!(
+            "the subscription should be completed after the last question, got {event}"
+        )),
+    }
+}
+
+async fn leaderboard_subscription(quiz: Subgraph) -> Result<(), String> {
+    let query = format!(
+        r#"subscription {{ leaderboardForQuiz(id: "{QUIZ_ID}") {{ list {{ id points }} }} }}"#
+    );
+    let mut leaderboards = quiz.subscribe(&query, json!({}), None).await?;
+    open_next_question(&quiz).await?;
+    send_answer(&quiz, "1", "0", GOOD_ANSWERS[0]).await?;
+    expect_eq(
+        "leaderboard sent after an answer",
+        &leaderboards.next().await?.unwrap_or_default(),
+        json!({ "leaderboardForQuiz": { "list": [{ "id": "1", "points": 1 }] } }),
+    )?;
+    open_next_question(&quiz).await?;
+    open_next_question(&quiz).await?;
+    match leaderboards.next().await? {
+        None => Ok(()),
+        Some(event) => Err(format!(
+            "the subscription should be completed after the last question, got {event}"
+        )),
+    }
+}
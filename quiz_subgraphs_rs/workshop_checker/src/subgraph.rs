@@ -0,0 +1,264 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::net::{SocketAddr, TcpListener};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use futures_util::{SinkExt, StreamExt};
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Method, Request};
+use serde_json::{json, Value};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+// Waiting longer for a response or an event means it won't come
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// A debug build of a subgraph starts in far less than that
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The binary of a subgraph running on a free port, killed when dropped
+pub(crate) struct Process {
+    child: Child,
+    addr: SocketAddr,
+    stderr_path: PathBuf,
+}
+
+impl Process {
+    pub(crate) async fn start(binary: &str) -> Result<Self, String> {
+        // The port is free again once the listener is dropped
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .map_err(|err| format!("cannot find a free port: {err}"))?;
+        let stderr_path =
+            std::env::temp_dir().join(format!("workshop-checker-{}.log", addr.port()));
+        let stderr = File::create(&stderr_path)
+            .map_err(|err| format!("cannot create {stderr_path:?}: {err}"))?;
+        let child = Command::new(binary)
+            .env("PORT", addr.port().to_string())
+            .stdout(Stdio::null())
+            .stderr(stderr)
+            .spawn()
+            .map_err(|err| format!("cannot start {binary}: {err}"))?;
+        let mut process = Self {
+            child,
+            addr,
+            stderr_path,
+        };
+
+        let started_at = Instant::now();
+        while TcpStream::connect(addr).await.is_err() {
+            if let Ok(Some(status)) = process.child.try_wait() {
+                return Err(format!("the subgraph exited with {status}"));
+            }
+            if started_at.elapsed() > STARTUP_TIMEOUT {
+                return Err(format!(
+                    "the subgraph doesn't listen on the PORT {}",
+                    addr.port()
+                ));
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        Ok(process)
+    }
+
+    pub(crate) fn subgraph(&self) -> Subgraph {
+        Subgraph::new(self.addr)
+    }
+
+    /// Stop the subgraph, returning where it panicked if it did
+    pub(crate) fn stop(mut self) -> Option<String> {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let stderr = std::fs::read_to_string(&self.stderr_path).unwrap_or_default();
+        // The panic message is on the line after the location
+        let mut lines = stderr.lines();
+        lines.find(|line| line.contains("panicked at")).map(|line| {
+            let location = &line[line.find("panicked at").unwrap_or_default()..];
+            let message = lines.next().unwrap_or_default();
+            format!("{location} {message}")
+        })
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = std::fs::remove_file(&self.stderr_path);
+    }
+}
+
+/// Client for a running subgraph
+#[derive(Clone)]
+pub struct Subgraph {
+    addr: SocketAddr,
+    client: Client<HttpConnector>,
+}
+
+impl Subgraph {
+    /// Client for the subgraph listening on `addr`
+    pub fn new(addr: SocketAddr) -> Self {
+        Self {
+            addr,
+            client: Client::new(),
+        }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}/", self.addr)
+    }
+
+    /// Data of a query or a mutation, sent with the `player` header when `player` is set, or
+    /// the message of its first GraphQL error
+    pub async fn execute(
+        &self,
+        query: &str,
+        variables: Value,
+        player: Option<&str>,
+    ) -> Result<Value, String> {
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(self.url())
+            .header(http::header::CONTENT_TYPE, "application/json");
+        if let Some(player) = player {
+            request = request.header("player", player);
+        }
+        let body = json!({ "query": query, "variables": variables });
+        let request = request
+            .body(Body::from(body.to_string()))
+            .map_err(|err| err.to_string())?;
+
+        let response = tokio::time::timeout(TIMEOUT, self.client.request(request))
+            .await
+            .map_err(|_| "no response in time".to_string())?
+            .map_err(|_| "no response, the subgraph panicked".to_string())?;
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(|_| "no response, the subgraph panicked".to_string())?;
+        let response: Value = serde_json::from_slice(&body)
+            .map_err(|_| format!("not a GraphQL response: {}", String::from_utf8_lossy(&body)))?;
+        match response["errors"][0]["message"].as_str() {
+            Some(error) => Err(error.to_string()),
+            None => Ok(response["data"].clone()),
+        }
+    }
+
+    /// Subscribe with the `graphql-transport-ws` protocol, `player` is sent in the
+    /// `connection_init` payload. Events sent after this returns are received
+    pub async fn subscribe(
+        &self,
+        query: &str,
+        variables: Value,
+        player: Option<&str>,
+    ) -> Result<Subscription, String> {
+        let mut request = format!("ws://{}/ws", self.addr)
+            .into_client_request()
+            .map_err(|err| err.to_string())?;
+        request.headers_mut().insert(
+            http::header::SEC_WEBSOCKET_PROTOCOL.as_str(),
+            HeaderValue::from_static("graphql-transport-ws"),
+        );
+        let (socket, _) = connect_async(request)
+            .await
+            .map_err(|err| format!("cannot connect to /ws: {err}"))?;
+
+        let mut subscription = Subscription {
+            socket,
+            received: VecDeque::new(),
+        };
+        subscription
+            .send(json!({ "type": "connection_init", "payload": { "player": player } }))
+            .await?;
+        let ack = subscription.receive().await?;
+        if ack["type"] != "connection_ack" {
+            return Err(format!("unexpected message {ack}"));
+        }
+        subscription
+            .send(json!({
+                "id": "1",
+                "type": "subscribe",
+                "payload": { "query": query, "variables": variables },
+            }))
+            .await?;
+        // The server answers a ping before running the resolver of the new subscription, but
+        // runs it before reading the next ping
+        for _ in 0..2 {
+            subscription.send(json!({ "type": "ping" })).await?;
+            subscription.wait_for_pong().await?;
+        }
+        Ok(subscription)
+    }
+}
+
+/// Events of a subscription, in the order they were sent
+pub struct Subscription {
+    socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    // Messages received while waiting for a pong
+    received: VecDeque<Value>,
+}
+
+impl Subscription {
+    /// Data of the next event, `None` once the server completed the subscription
+    pub async fn next(&mut self) -> Result<Option<Value>, String> {
+        let message = match self.received.pop_front() {
+            Some(message) => message,
+            None => self.receive().await?,
+        };
+        match message["type"].as_str() {
+            Some("next") => match message["payload"]["errors"][0]["message"].as_str() {
+                Some(error) => Err(error.to_string()),
+                None => Ok(Some(message["payload"]["data"].clone())),
+            },
+            Some("complete") => Ok(None),
+            Some("error") => Err(message["payload"][0]["message"]
+                .as_str()
+                .unwrap_or("error in the subscription")
+                .to_string()),
+            _ => Err(format!("unexpected message {message}")),
+        }
+    }
+
+    /// Data of every event until the server completes the subscription
+    pub async fn collect(mut self) -> Result<Vec<Value>, String> {
+        let mut events = Vec::new();
+        while let Some(event) = self.next().await? {
+            events.push(event);
+        }
+        Ok(events)
+    }
+
+    async fn send(&mut self, message: Value) -> Result<(), String> {
+        self.socket
+            .send(Message::text(message.to_string()))
+            .await
+            .map_err(|err| err.to_string())
+    }
+
+    async fn wait_for_pong(&mut self) -> Result<(), String> {
+        loop {
+            let message = self.receive().await?;
+            if message["type"] == "pong" {
+                return Ok(());
+            }
+            self.received.push_back(message);
+        }
+    }
+
+    // Next GraphQL message, the WebSocket pings are answered by tungstenite
+    async fn receive(&mut self) -> Result<Value, String> {
+        loop {
+            let message = tokio::time::timeout(TIMEOUT, self.socket.next())
+                .await
+                .map_err(|_| "no event in time".to_string())?
+                .ok_or("the subgraph closed the WebSocket, it probably panicked")?
+                .map_err(|err| err.to_string())?;
+            if let Message::Text(text) = message {
+                return serde_json::from_str(&text).map_err(|err| err.to_string());
+            }
+        }
+    }
+}